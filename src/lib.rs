@@ -1,5 +1,6 @@
 use nih_plug::prelude::*;
 use nih_plug::util::db_to_gain;
+use nih_plug::wrapper::state::{ParamValue, PluginState};
 use nih_plug_egui::{
     EguiState, create_egui_editor, egui,
     egui::emath,
@@ -7,10 +8,13 @@ use nih_plug_egui::{
     resizable_window::ResizableWindow,
     widgets,
 };
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use triple_buffer::TripleBuffer;
 
-#[derive(Clone, Enum, PartialEq)]
+#[derive(Clone, Copy, Enum, PartialEq)]
 pub enum Fx {
     #[id = "panning"]
     Panning,
@@ -32,6 +36,18 @@ pub enum Fx {
 
     #[id = "chorus"]
     Chorus,
+
+    #[id = "analyzer"]
+    Analyzer,
+
+    #[id = "monitor"]
+    Monitor,
+
+    #[id = "imager"]
+    Imager,
+
+    #[id = "haas"]
+    Haas,
 }
 
 impl Fx {
@@ -44,11 +60,25 @@ impl Fx {
             Fx::Distortion => 4.0,
             Fx::Delay => 5.0,
             Fx::Chorus => 6.0,
+            Fx::Analyzer => 7.0,
+            Fx::Monitor => 8.0,
+            Fx::Imager => 9.0,
+            Fx::Haas => 10.0,
         }
     }
 
+    /// Effects that change the audio and can therefore run on mid and side, as opposed to the
+    /// meters and the mid/side utilities themselves.
+    pub fn supports_mid_side(&self) -> bool {
+        matches!(self, Fx::Distortion | Fx::Delay | Fx::Chorus)
+    }
+
     pub fn from_f32(i: f32) -> Self {
         match i {
+            10.0 => Fx::Haas,
+            9.0 => Fx::Imager,
+            8.0 => Fx::Monitor,
+            7.0 => Fx::Analyzer,
             6.0 => Fx::Chorus,
             5.0 => Fx::Delay,
             4.0 => Fx::Distortion,
@@ -60,8 +90,80 @@ impl Fx {
     }
 }
 
-#[derive(Clone)]
-pub struct UiState {}
+#[derive(Clone, Default)]
+pub struct UiState {
+    pub distortion: DistortionDisplay,
+    pub goniometer: GoniometerDisplay,
+    pub spectrum: SpectrumDisplay,
+    pub spectrogram: SpectrogramDisplay,
+    pub oscilloscope: OscilloscopeDisplay,
+    pub input_level: LevelDisplay,
+    pub output_level: LevelDisplay,
+}
+
+/// How the analyzers and meters are shown. None of this changes the audio, so it's saved along
+/// with the editor state instead of being exposed to the host as automatable parameters.
+#[derive(Clone, Copy, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ViewSettings {
+    pub goniometer_overlay: bool,
+    pub goniometer_tap: AnalyzerTap,
+    /// Milliseconds for the phosphor trails to fade out.
+    pub goniometer_persistence: f32,
+    pub goniometer_rendering: GoniometerRendering,
+    pub goniometer_heatmap: bool,
+    pub goniometer_auto_gain: bool,
+    /// Integration time of the correlation and balance meters in milliseconds.
+    pub stereo_meter_integration: f32,
+    pub loudness_meter: bool,
+    pub analyzer_view: SpectrumView,
+    pub analyzer_fft_size: FftSize,
+    pub analyzer_window: SpectrumWindow,
+    /// Time constant of the spectrum averaging in milliseconds.
+    pub analyzer_averaging: f32,
+    /// Display tilt in dB per octave.
+    pub analyzer_slope: f32,
+    pub analyzer_peak_hold: bool,
+    pub spectrogram: bool,
+    pub spectrogram_color_map: SpectrogramColorMap,
+    /// Dynamic range of the spectrogram in dB.
+    pub spectrogram_range: f32,
+    pub oscilloscope: bool,
+    /// Length of the oscilloscope trace in milliseconds.
+    pub oscilloscope_window: f32,
+    pub oscilloscope_trigger: bool,
+    pub oscilloscope_trigger_level: f32,
+    pub oscilloscope_show_input: bool,
+}
+
+impl Default for ViewSettings {
+    fn default() -> Self {
+        Self {
+            goniometer_overlay: false,
+            goniometer_tap: AnalyzerTap::Post,
+            goniometer_persistence: 250.0,
+            goniometer_rendering: GoniometerRendering::Dots,
+            goniometer_heatmap: true,
+            goniometer_auto_gain: false,
+            stereo_meter_integration: 300.0,
+            loudness_meter: false,
+            analyzer_view: SpectrumView::LeftRight,
+            analyzer_fft_size: FftSize::S4096,
+            analyzer_window: SpectrumWindow::Hann,
+            analyzer_averaging: 300.0,
+            analyzer_slope: 4.5,
+            analyzer_peak_hold: false,
+            spectrogram: false,
+            spectrogram_color_map: SpectrogramColorMap::Heat,
+            spectrogram_range: 90.0,
+            oscilloscope: false,
+            oscilloscope_window: 10.0,
+            oscilloscope_trigger: true,
+            oscilloscope_trigger_level: 0.0,
+            oscilloscope_show_input: true,
+        }
+    }
+}
 
 #[derive(Clone, Enum, PartialEq)]
 pub enum DistortionMode {
@@ -85,6 +187,27 @@ pub enum DistortionMode {
 
     #[id = "bit-crush"]
     BitCrush,
+
+    #[id = "custom"]
+    Custom,
+
+    #[id = "triode"]
+    Triode,
+
+    #[id = "tape"]
+    Tape,
+
+    #[id = "sine-fold"]
+    SineFold,
+
+    #[id = "triangle-fold"]
+    TriangleFold,
+
+    #[id = "half-wave-rect"]
+    HalfWaveRectifier,
+
+    #[id = "full-wave-rect"]
+    FullWaveRectifier,
 }
 
 impl DistortionMode {
@@ -97,11 +220,25 @@ impl DistortionMode {
             DistortionMode::PieceWiseOverDrive => 4.0,
             DistortionMode::Diode => 5.0,
             DistortionMode::BitCrush => 6.0,
+            DistortionMode::Custom => 7.0,
+            DistortionMode::Triode => 8.0,
+            DistortionMode::Tape => 9.0,
+            DistortionMode::SineFold => 10.0,
+            DistortionMode::TriangleFold => 11.0,
+            DistortionMode::HalfWaveRectifier => 12.0,
+            DistortionMode::FullWaveRectifier => 13.0,
         }
     }
 
     pub fn from_f32(i: f32) -> Self {
         match i {
+            13.0 => DistortionMode::FullWaveRectifier,
+            12.0 => DistortionMode::HalfWaveRectifier,
+            11.0 => DistortionMode::TriangleFold,
+            10.0 => DistortionMode::SineFold,
+            9.0 => DistortionMode::Tape,
+            8.0 => DistortionMode::Triode,
+            7.0 => DistortionMode::Custom,
             6.0 => DistortionMode::BitCrush,
             5.0 => DistortionMode::Diode,
             4.0 => DistortionMode::PieceWiseOverDrive,
@@ -111,6 +248,199 @@ impl DistortionMode {
             _ => DistortionMode::Cubic,
         }
     }
+
+    /// Whether the shaper has closed form antiderivatives, see [`Adaa`].
+    pub fn supports_adaa(&self) -> bool {
+        matches!(
+            self,
+            DistortionMode::Cubic
+                | DistortionMode::ArcTangent
+                | DistortionMode::InfiniteClipping
+                | DistortionMode::ExponentialSoftClipping
+                | DistortionMode::SineFold
+                | DistortionMode::HalfWaveRectifier
+                | DistortionMode::FullWaveRectifier
+        )
+    }
+}
+
+#[derive(Clone, Copy, Enum, PartialEq)]
+pub enum Oversampling {
+    #[id = "1x"]
+    #[name = "1x"]
+    X1,
+
+    #[id = "2x"]
+    #[name = "2x"]
+    X2,
+
+    #[id = "4x"]
+    #[name = "4x"]
+    X4,
+
+    #[id = "8x"]
+    #[name = "8x"]
+    X8,
+
+    #[id = "16x"]
+    #[name = "16x"]
+    X16,
+}
+
+impl Oversampling {
+    /// Number of cascaded 2x halfband stages needed to reach this factor.
+    pub fn stages(&self) -> usize {
+        match self {
+            Oversampling::X1 => 0,
+            Oversampling::X2 => 1,
+            Oversampling::X4 => 2,
+            Oversampling::X8 => 3,
+            Oversampling::X16 => 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Enum, PartialEq)]
+pub enum Antialiasing {
+    #[id = "off"]
+    #[name = "Off"]
+    Off,
+
+    #[id = "adaa-1"]
+    #[name = "ADAA 1st Order"]
+    FirstOrder,
+
+    #[id = "adaa-2"]
+    #[name = "ADAA 2nd Order"]
+    SecondOrder,
+}
+
+/// Where the analyzers pick up the signal when they're shown next to an effect.
+#[derive(Clone, Copy, Deserialize, Enum, PartialEq, Serialize)]
+pub enum AnalyzerTap {
+    #[id = "pre"]
+    #[name = "Pre"]
+    Pre,
+
+    #[id = "post"]
+    #[name = "Post"]
+    Post,
+}
+
+#[derive(Clone, Copy, Deserialize, Enum, PartialEq, Serialize)]
+pub enum SpectrogramColorMap {
+    #[id = "heat"]
+    #[name = "Heat"]
+    Heat,
+
+    #[id = "inferno"]
+    #[name = "Inferno"]
+    Inferno,
+
+    #[id = "grayscale"]
+    #[name = "Grayscale"]
+    Grayscale,
+}
+
+impl SpectrogramColorMap {
+    /// Maps a level between 0 and 1 to a color.
+    pub fn color(&self, level: f32) -> Color32 {
+        match self {
+            SpectrogramColorMap::Heat => heatmap_color(level),
+            SpectrogramColorMap::Inferno => gradient_color(
+                &[
+                    [0.0, 0.0, 0.02],
+                    [0.26, 0.04, 0.41],
+                    [0.58, 0.15, 0.4],
+                    [0.87, 0.32, 0.23],
+                    [0.99, 0.65, 0.04],
+                    [0.99, 1.0, 0.64],
+                ],
+                level,
+            ),
+            SpectrogramColorMap::Grayscale => gradient_color(&[[0.0; 3], [1.0; 3]], level),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Enum, PartialEq, Serialize)]
+pub enum GoniometerRendering {
+    #[id = "dots"]
+    #[name = "Dots"]
+    Dots,
+
+    #[id = "lines"]
+    #[name = "Lines"]
+    Lines,
+}
+
+#[derive(Clone, Copy, Enum, PartialEq)]
+pub enum TapeSpeed {
+    #[id = "7.5-ips"]
+    #[name = "7.5 ips"]
+    Ips7_5,
+
+    #[id = "15-ips"]
+    #[name = "15 ips"]
+    Ips15,
+
+    #[id = "30-ips"]
+    #[name = "30 ips"]
+    Ips30,
+}
+
+impl TapeSpeed {
+    /// Slower tape needs more record emphasis, so the highs saturate earlier.
+    pub fn emphasis_db(&self) -> f32 {
+        match self {
+            TapeSpeed::Ips7_5 => 9.0,
+            TapeSpeed::Ips15 => 6.0,
+            TapeSpeed::Ips30 => 3.0,
+        }
+    }
+
+    /// Where the playback high frequency loss kicks in.
+    pub fn loss_frequency(&self) -> f32 {
+        match self {
+            TapeSpeed::Ips7_5 => 10_000.0,
+            TapeSpeed::Ips15 => 15_000.0,
+            TapeSpeed::Ips30 => 20_000.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Enum, PartialEq)]
+pub enum HaasChannel {
+    #[id = "left"]
+    #[name = "Left"]
+    Left,
+
+    #[id = "right"]
+    #[name = "Right"]
+    Right,
+}
+
+#[derive(Clone, Copy, Enum, PartialEq)]
+pub enum MonitorMode {
+    #[id = "stereo"]
+    #[name = "Stereo"]
+    Stereo,
+
+    #[id = "mid"]
+    #[name = "Mid"]
+    Mid,
+
+    #[id = "side"]
+    #[name = "Side"]
+    Side,
+
+    #[id = "swap"]
+    #[name = "Swap"]
+    Swap,
+
+    #[id = "mono"]
+    #[name = "Mono"]
+    Mono,
 }
 
 #[derive(Clone, Enum, PartialEq)]
@@ -123,6 +453,9 @@ pub enum PanningMode {
 
     #[id = "sine"]
     Sine,
+
+    #[id = "compromise"]
+    Compromise,
 }
 
 impl PanningMode {
@@ -131,16 +464,58 @@ impl PanningMode {
             PanningMode::Linear => 0.0,
             PanningMode::Square => 1.0,
             PanningMode::Sine => 2.0,
+            PanningMode::Compromise => 3.0,
         }
     }
 
     pub fn from_f32(i: f32) -> Self {
         match i {
+            3.0 => PanningMode::Compromise,
             2.0 => PanningMode::Sine,
             1.0 => PanningMode::Square,
             _ => PanningMode::Linear,
         }
     }
+
+    /// Gain of the right channel for a position between 0 (hard left) and 1 (hard right), the
+    /// left channel gets the gain at `1 - position`. In the center linear is 6 dB down, square
+    /// and sine are 3 dB down and compromise, the geometric mean of linear and sine, sits at
+    /// 4.5 dB down.
+    pub fn pan_gain(&self, position: f32) -> f32 {
+        let position = position.clamp(0.0, 1.0);
+        match self {
+            PanningMode::Linear => position,
+            PanningMode::Square => position.sqrt(),
+            PanningMode::Sine => (position * (std::f32::consts::PI / 2.0)).sin(),
+            PanningMode::Compromise => {
+                (position * (position * (std::f32::consts::PI / 2.0)).sin()).sqrt()
+            }
+        }
+    }
+}
+
+/// What kind of signal the panner expects.
+#[derive(Clone, Copy, Enum, PartialEq)]
+pub enum PanningSource {
+    /// A mono source placed in the stereo field, each channel is scaled independently.
+    #[id = "mono"]
+    #[name = "Mono"]
+    Mono,
+
+    /// Turns down the far channel of a stereo source, the near channel stays as it is.
+    #[id = "balance"]
+    #[name = "Balance"]
+    Balance,
+
+    /// Pans both channels of a stereo source as two mono sources, narrowing and moving the image.
+    #[id = "stereo"]
+    #[name = "Stereo"]
+    Stereo,
+
+    /// Places a mono source anywhere around the listener for headphone playback.
+    #[id = "binaural"]
+    #[name = "Binaural"]
+    Binaural,
 }
 
 pub struct Chorus {
@@ -149,12 +524,61 @@ pub struct Chorus {
     lfos: Vec<f32>,
 }
 
+/// Per block settings for [`Chorus::process`].
+pub struct ChorusSettings {
+    pub voice_count: usize,
+    pub rate_radians: f32,
+    pub sample_length: f32,
+    pub spread: f32,
+    pub dry_out: f32,
+    pub wet_mix: f32,
+}
+
 impl Chorus {
     pub fn initialize_lfos(&mut self, voice_count: usize) {
        for voice in 0..voice_count {
             self.lfos[voice as usize] = (voice + 1) as f32 / voice_count as f32 * std::f32::consts::PI;
         }
     }
+
+    pub fn process(&mut self, input: f32, settings: &ChorusSettings) -> f32 {
+        let sample_length = settings.sample_length;
+        let mut out = input * settings.dry_out;
+
+        // Take care of circular buffer
+        let chorus_idx = self.buffer_idx % sample_length as usize;
+
+        /*
+            Main algorithm
+            - For each LFO, calculate the LFO oscillator position, which will most likely not be an integer value
+                - LERP the output value to be the current chorus buffer position with the next one.
+                - Add the LERPed value to the dry mixed value and set that as the output sample
+        */
+        for voice_idx in 0..settings.voice_count {
+            // TODO - Experiment with different LFO shapes and numerically stable oscillator(phasor)
+            self.lfos[voice_idx] += settings.rate_radians; // Always incrementing instead of being between bound 0..2pi... :(
+            let mut chorus_pos: f32 = chorus_idx as f32 - (0.5 + 0.49 * self.lfos[voice_idx].sin()) * (voice_idx + 1) as f32 * settings.spread;
+
+            if chorus_pos < 0.0 {
+                chorus_pos += sample_length;
+            }
+
+            let mut next_chorus_pos = chorus_pos + 1.0;
+
+            if chorus_pos >= sample_length - 1.0 {
+                next_chorus_pos = 0.0;
+            }
+
+            // Out will accumulate the interpolated values from the chorus delay buffer which are calculated by positions of the lfo voice
+            let fractional = chorus_pos.fract();
+            out += settings.wet_mix * (self.buffer[chorus_pos as usize] * (1.0 - fractional) + self.buffer[next_chorus_pos as usize] * fractional);
+        }
+
+        self.buffer[chorus_idx] = input;
+        self.buffer_idx = (self.buffer_idx + 1) % sample_length as usize;
+
+        out
+    }
 }
 
 impl Default for Chorus {
@@ -184,163 +608,2853 @@ impl Default for DelayBuffer {
     }
 }
 
-pub struct OutputBuffer {
-    pub left: [f32; 4096],
-    pub right: [f32; 4096],
+// Longest delay the Haas widener can be set to
+const HAAS_MAX_DELAY_MS: f32 = 40.0;
+
+/// Haas widener state. Both channels are written every block so switching the delayed channel
+/// picks up with its own history instead of the other channel's.
+#[derive(Default)]
+pub struct Haas {
+    delay: [Vec<f32>; 2],
+    delay_index: usize,
+    channel: Option<HaasChannel>,
+    highpass: Biquad,
+    lowpass: Biquad,
+    pub stereo_meter: StereoMeter,
+}
+
+impl Haas {
+    /// Sizes the delay lines for the longest delay at the host's rate.
+    pub fn initialize(&mut self, sample_rate: f32) {
+        let capacity = (sample_rate * HAAS_MAX_DELAY_MS / 1000.0).ceil() as usize + 1;
+        for delay in self.delay.iter_mut() {
+            delay.resize(capacity, 0.0);
+        }
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        for delay in self.delay.iter_mut() {
+            delay.fill(0.0);
+        }
+        self.delay_index = 0;
+        self.highpass.reset();
+        self.lowpass.reset();
+    }
+}
+
+// Samples kept for the goniometer, a bit over 300 ms at 48 kHz. Must be a power of two.
+const GONIOMETER_RING_CAPACITY: usize = 1 << 14;
+
+/// Lock free ring buffer of stereo sample pairs. The audio thread pushes however many samples the
+/// host's block has and the editor picks up everything written since its last read, so neither
+/// side depends on the block size and neither ever blocks. Samples are stored as their bits in
+/// atomics, so a reader that falls a whole buffer behind sees newer samples rather than torn ones.
+pub struct SampleRing {
+    left: Vec<AtomicU32>,
+    right: Vec<AtomicU32>,
+    /// Total number of pairs ever written, the last `min(written, capacity)` of them are valid.
+    written: AtomicUsize,
+}
+
+impl SampleRing {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity.is_power_of_two());
+
+        Self {
+            left: (0..capacity).map(|_| AtomicU32::new(0)).collect(),
+            right: (0..capacity).map(|_| AtomicU32::new(0)).collect(),
+            written: AtomicUsize::new(0),
+        }
+    }
+
+    /// Only one thread may push at a time.
+    pub fn push(&self, left: f32, right: f32) {
+        let written = self.written.load(Ordering::Relaxed);
+        let idx = written & (self.left.len() - 1);
+        self.left[idx].store(left.to_bits(), Ordering::Relaxed);
+        self.right[idx].store(right.to_bits(), Ordering::Relaxed);
+        self.written.store(written + 1, Ordering::Release);
+    }
+
+    /// Appends the pairs written since `position` to `left` and `right`, or only the most recent
+    /// ones if more than the capacity has been written in the meantime. Returns the position to
+    /// pass to the next call.
+    pub fn read_since(&self, position: usize, left: &mut Vec<f32>, right: &mut Vec<f32>) -> usize {
+        let written = self.written.load(Ordering::Acquire);
+        let capacity = self.left.len();
+        let start = position.max(written.saturating_sub(capacity)).min(written);
+
+        for sample in start..written {
+            let idx = sample & (capacity - 1);
+            left.push(f32::from_bits(self.left[idx].load(Ordering::Relaxed)));
+            right.push(f32::from_bits(self.right[idx].load(Ordering::Relaxed)));
+        }
+
+        written
+    }
+}
+
+// Samples the oscilloscope keeps around, enough for the longest window plus a trigger search
+const OSCILLOSCOPE_RING_CAPACITY: usize = 1 << 15;
+
+/// Mono input and output of the selected effect, shared with the editor's oscilloscope. The pairs
+/// in the ring hold the input on the left and the output on the right.
+pub struct Oscilloscope {
+    pub samples: SampleRing,
+    sample_rate: AtomicU32,
+    /// How far the output lags behind the input, so the editor can line the two traces up.
+    latency: AtomicU32,
 }
 
-impl Default for OutputBuffer {
+impl Default for Oscilloscope {
     fn default() -> Self {
         Self {
-            left: [0.0; 4096],
-            right: [0.0; 4096],
+            samples: SampleRing::new(OSCILLOSCOPE_RING_CAPACITY),
+            sample_rate: AtomicU32::new(44100.0_f32.to_bits()),
+            latency: AtomicU32::new(0),
         }
     }
 }
 
-/// This is mostly identical to the gain example, minus some fluff, and with a GUI.
-pub struct HackAudio {
-    params: Arc<PluginParams>,
-    ui_state: UiState,
-    host_sample_rate: f32,
-    goniometer_input: triple_buffer::Input<OutputBuffer>,
-    goniometer_output: Arc<Mutex<triple_buffer::Output<OutputBuffer>>>,
-    delay_buffer: DelayBuffer,
-    chorus: Chorus,
+impl Oscilloscope {
+    pub fn sample_rate(&self) -> f32 {
+        f32::from_bits(self.sample_rate.load(Ordering::Relaxed))
+    }
+
+    pub fn latency(&self) -> usize {
+        self.latency.load(Ordering::Relaxed) as usize
+    }
+
+    /// Only called from the audio thread, after the effect has processed the block that
+    /// `input` was captured from.
+    pub fn push(&self, sample_rate: f32, latency: u32, input: &[f32], output: &[&mut [f32]]) {
+        self.sample_rate
+            .store(sample_rate.to_bits(), Ordering::Relaxed);
+        self.latency.store(latency, Ordering::Relaxed);
+
+        for (sample_idx, input) in input.iter().enumerate() {
+            self.samples.push(*input, mono_sample(output, sample_idx));
+        }
+    }
 }
 
-#[derive(Params)]
-pub struct PluginParams {
-    /// The editor state, saved together with the parameter state so the custom scaling can be
-    /// restored.
-    #[persist = "editor-state"]
-    editor_state: Arc<EguiState>,
+/// Average of all channels at `sample_idx`, so a panned signal still shows up.
+pub fn mono_sample(channels: &[&mut [f32]], sample_idx: usize) -> f32 {
+    channels
+        .iter()
+        .map(|channel| channel[sample_idx])
+        .sum::<f32>()
+        / channels.len().max(1) as f32
+}
 
-    #[id = "selected_fx"]
-    pub selected_fx: EnumParam<Fx>,
+// RMS integration time of the level meters
+const LEVEL_METER_RMS_MS: f32 = 300.0;
 
-    #[id = "pan"]
-    pub pan: FloatParam,
+/// Peak and RMS levels of a stereo signal, shared between the audio thread and the editor. Peaks
+/// pile up until the editor takes them, so a single clipped sample can't slip between two frames.
+#[derive(Default)]
+pub struct LevelMeter {
+    peaks: [AtomicU32; 2],
+    rms: [AtomicU32; 2],
+}
 
-    #[id = "panning_mode"]
-    pub panning_mode: EnumParam<PanningMode>,
+impl LevelMeter {
+    /// Only called from the audio thread. Non-negative floats sort the same as their bits, which
+    /// makes the integer `fetch_max` a float max.
+    pub fn update(&self, channel: usize, peak: f32, rms: f32) {
+        self.peaks[channel].fetch_max(peak.to_bits(), Ordering::Relaxed);
+        self.rms[channel].store(rms.to_bits(), Ordering::Relaxed);
+    }
 
-    #[id = "mid-side-encoding-stereo-width"]
-    pub mid_side_enc_stereo_width: FloatParam,
+    /// Returns the highest peak since the last call along with the current RMS level.
+    pub fn take(&self, channel: usize) -> (f32, f32) {
+        (
+            f32::from_bits(self.peaks[channel].swap(0, Ordering::Relaxed)),
+            f32::from_bits(self.rms[channel].load(Ordering::Relaxed)),
+        )
+    }
+}
 
-    #[id = "distortion_mode"]
-    pub distortion_mode: EnumParam<DistortionMode>,
+/// Audio side of a [`LevelMeter`], integrating the RMS levels across blocks.
+#[derive(Default)]
+pub struct LevelDetector {
+    mean_squares: [f32; 2],
+}
 
-    #[id = "distortion_amount"]
-    pub distortion_amount: FloatParam,
+impl LevelDetector {
+    pub fn process(&mut self, sample_rate: f32, channels: &[&mut [f32]], meter: &LevelMeter) {
+        let Some(last_channel) = channels.len().checked_sub(1) else {
+            return;
+        };
+        let coefficient = 1.0 - (-1000.0 / (LEVEL_METER_RMS_MS * sample_rate)).exp();
+
+        for (channel, mean_square) in self.mean_squares.iter_mut().enumerate() {
+            // A mono signal shows up on both bars
+            let samples = &channels[channel.min(last_channel)];
+            let mut peak = 0.0_f32;
+            for sample in samples.iter() {
+                peak = peak.max(sample.abs());
+                *mean_square += (sample * sample - *mean_square) * coefficient;
+            }
 
-    #[id = "bit-crush-bits"]
-    pub bit_crush_bits: IntParam,
+            meter.update(channel, peak, mean_square.sqrt());
+        }
+    }
+}
 
-    #[id = "distortion-mix"]
-    pub distortion_mix: FloatParam,
+/// What the correlation and balance meters show, sent to the editor once per block.
+#[derive(Clone, Copy, Default)]
+pub struct StereoMeterReadout {
+    /// Phase correlation from -1 (out of phase) to +1 (mono).
+    pub correlation: f32,
+    /// Where the energy sits, from -1 (hard left) to +1 (hard right).
+    pub balance: f32,
+}
 
-    // TODO: Make all of the delay params FloatParam
-    // Then skew the Range so the top half has more play
-    // Also need to step by 1.0
-    #[id = "delay-feedback"]
-    pub delay_feedback: IntParam,
+// Largest FFT the spectrum analyzer can use, every other size is a power of two below this
+const MAX_FFT_SIZE: usize = 16384;
+// The spectrum is reduced to this many log spaced points before it's sent to the editor
+pub const SPECTRUM_POINTS: usize = 512;
+const SPECTRUM_MIN_HZ: f32 = 20.0;
+const SPECTRUM_MAX_HZ: f32 = 20_000.0;
+// Most samples between two FFT frames, so the large sizes still update smoothly
+const SPECTRUM_MAX_HOP: usize = 2048;
+// Level used for points without any signal and for points above Nyquist
+pub const SPECTRUM_FLOOR_DB: f32 = -200.0;
+
+#[derive(Clone, Copy, Deserialize, Enum, PartialEq, Serialize)]
+pub enum FftSize {
+    #[id = "1024"]
+    #[name = "1024"]
+    S1024,
+
+    #[id = "2048"]
+    #[name = "2048"]
+    S2048,
+
+    #[id = "4096"]
+    #[name = "4096"]
+    S4096,
+
+    #[id = "8192"]
+    #[name = "8192"]
+    S8192,
+
+    #[id = "16384"]
+    #[name = "16384"]
+    S16384,
+}
 
-    #[id = "delay-time"]
-    pub delay_time: IntParam,
+impl FftSize {
+    pub fn samples(&self) -> usize {
+        match self {
+            FftSize::S1024 => 1024,
+            FftSize::S2048 => 2048,
+            FftSize::S4096 => 4096,
+            FftSize::S8192 => 8192,
+            FftSize::S16384 => 16384,
+        }
+    }
+}
 
-    #[id = "delay-mix-in"]
-    pub delay_mix_in: IntParam,
+#[derive(Clone, Copy, Deserialize, Enum, PartialEq, Serialize)]
+pub enum SpectrumWindow {
+    #[id = "rectangular"]
+    #[name = "Rectangular"]
+    Rectangular,
 
-    #[id = "delay-wet-out"]
-    pub delay_wet_out: IntParam,
+    #[id = "hann"]
+    #[name = "Hann"]
+    Hann,
 
-    #[id = "delay-dry-out"]
-    pub delay_dry_out: IntParam,
+    #[id = "blackman-harris"]
+    #[name = "Blackman-Harris"]
+    BlackmanHarris,
 
-    #[id = "chorus-time"]
-    pub chorus_time: FloatParam,
+    #[id = "flat-top"]
+    #[name = "Flat Top"]
+    FlatTop,
+}
 
-    #[id = "chorus-voice-count"]
-    pub chorus_voice_count: IntParam,
+impl SpectrumWindow {
+    /// Generalized cosine window coefficients.
+    fn coefficients(&self) -> &'static [f32] {
+        match self {
+            SpectrumWindow::Rectangular => &[1.0],
+            SpectrumWindow::Hann => &[0.5, 0.5],
+            SpectrumWindow::BlackmanHarris => &[0.35875, 0.48829, 0.14128, 0.01168],
+            SpectrumWindow::FlatTop => &[
+                0.215_578_95,
+                0.416_631_6,
+                0.277_263_16,
+                0.083_578_95,
+                0.006_947_368,
+            ],
+        }
+    }
 
-    #[id = "chorus-rate"]
-    pub chorus_rate: FloatParam,
+    pub fn value(&self, idx: usize, len: usize) -> f32 {
+        let phase = std::f32::consts::TAU * idx as f32 / len as f32;
+        self.coefficients()
+            .iter()
+            .enumerate()
+            .map(|(term, coefficient)| {
+                let sign = if term % 2 == 0 { 1.0 } else { -1.0 };
+                sign * coefficient * (term as f32 * phase).cos()
+            })
+            .sum()
+    }
+}
 
-    #[id = "chorus-depth"]
-    pub chorus_depth: FloatParam,
+/// Which of the analyzed signals the spectrum shows. The indices match [`SpectrumFrame::levels`].
+#[derive(Clone, Copy, Deserialize, Enum, PartialEq, Serialize)]
+pub enum SpectrumView {
+    #[id = "left-right"]
+    #[name = "L/R"]
+    LeftRight,
 
-    #[id = "chorus-out-mix-wet"]
-    pub chorus_out_mix_wet: FloatParam,
+    #[id = "mid-side"]
+    #[name = "M/S"]
+    MidSide,
 
-    #[id = "chorus-out-mix-dry"]
-    pub chorus_out_mix_dry: FloatParam,
+    #[id = "left"]
+    #[name = "L"]
+    Left,
+
+    #[id = "right"]
+    #[name = "R"]
+    Right,
+
+    #[id = "mid"]
+    #[name = "M"]
+    Mid,
+
+    #[id = "side"]
+    #[name = "S"]
+    Side,
 }
 
+impl SpectrumView {
+    pub fn channels(&self) -> &'static [usize] {
+        match self {
+            SpectrumView::LeftRight => &[0, 1],
+            SpectrumView::MidSide => &[2, 3],
+            SpectrumView::Left => &[0],
+            SpectrumView::Right => &[1],
+            SpectrumView::Mid => &[2],
+            SpectrumView::Side => &[3],
+        }
+    }
+}
 
-impl Default for HackAudio {
+/// Frequency of a spectrum point, the points are spaced logarithmically.
+pub fn spectrum_point_frequency(point: f32) -> f32 {
+    SPECTRUM_MIN_HZ * (SPECTRUM_MAX_HZ / SPECTRUM_MIN_HZ).powf(point / (SPECTRUM_POINTS - 1) as f32)
+}
+
+/// In place iterative radix-2 FFT for power of two sizes up to `MAX_FFT_SIZE`. The twiddle factors
+/// are computed once for the largest size, and smaller sizes step through them faster.
+pub struct Fft {
+    cos: Vec<f32>,
+    sin: Vec<f32>,
+}
+
+impl Default for Fft {
+    fn default() -> Self {
+        let (cos, sin) = (0..MAX_FFT_SIZE / 2)
+            .map(|idx| {
+                let phase = -std::f32::consts::TAU * idx as f32 / MAX_FFT_SIZE as f32;
+                (phase.cos(), phase.sin())
+            })
+            .unzip();
+
+        Self { cos, sin }
+    }
+}
+
+impl Fft {
+    pub fn process(&self, real: &mut [f32], imag: &mut [f32]) {
+        let len = real.len();
+        debug_assert!(len.is_power_of_two() && len <= MAX_FFT_SIZE && imag.len() == len);
+
+        let bits = len.trailing_zeros();
+        for idx in 0..len {
+            let reversed = idx.reverse_bits() >> (usize::BITS - bits);
+            if reversed > idx {
+                real.swap(idx, reversed);
+                imag.swap(idx, reversed);
+            }
+        }
+
+        let mut size = 2;
+        while size <= len {
+            let half = size / 2;
+            let stride = MAX_FFT_SIZE / size;
+            for start in (0..len).step_by(size) {
+                for offset in 0..half {
+                    let (cos, sin) = (self.cos[offset * stride], self.sin[offset * stride]);
+                    let (even, odd) = (start + offset, start + offset + half);
+                    let odd_real = real[odd] * cos - imag[odd] * sin;
+                    let odd_imag = real[odd] * sin + imag[odd] * cos;
+
+                    real[odd] = real[even] - odd_real;
+                    imag[odd] = imag[even] - odd_imag;
+                    real[even] += odd_real;
+                    imag[even] += odd_imag;
+                }
+            }
+            size *= 2;
+        }
+    }
+}
+
+/// Spectrum levels in dB for the left, right, mid and side signals, sent to the editor through a
+/// triple buffer whenever the analyzer finishes an FFT frame.
+#[derive(Clone, Copy)]
+pub struct SpectrumFrame {
+    pub levels: [[f32; SPECTRUM_POINTS]; 4],
+}
+
+impl Default for SpectrumFrame {
     fn default() -> Self {
-        let (goniometer_input, goniometer_output) = TripleBuffer::default().split();
         Self {
-            params: Arc::new(PluginParams::default()),
-            goniometer_input: goniometer_input,
-            goniometer_output: Arc::new(Mutex::new(goniometer_output)),
-            delay_buffer: DelayBuffer::default(),
-            chorus: Chorus::default(),
-            host_sample_rate: 44100.0,
-            ui_state: UiState {},
+            levels: [[SPECTRUM_FLOOR_DB; SPECTRUM_POINTS]; 4],
         }
     }
 }
 
-impl Default for PluginParams {
+/// Settings for the [`SpectrumAnalyzer`], resolved from the parameters once per block.
+pub struct SpectrumSettings {
+    pub fft_size: usize,
+    pub window: SpectrumWindow,
+    pub averaging_ms: f32,
+    pub channels: &'static [usize],
+}
+
+/// Collects the input and turns it into a [`SpectrumFrame`] every hop. Everything is allocated up
+/// front, so this is safe to run on the audio thread.
+pub struct SpectrumAnalyzer {
+    fft: Fft,
+    history: [Vec<f32>; 2],
+    position: usize,
+    since_last_frame: usize,
+    window: Vec<f32>,
+    window_size: usize,
+    window_kind: SpectrumWindow,
+    window_gain: f32,
+    real: Vec<f32>,
+    imag: Vec<f32>,
+    averaged: [[f32; SPECTRUM_POINTS]; 4],
+    unaveraged: SpectrumFrame,
+}
+
+impl Default for SpectrumAnalyzer {
     fn default() -> Self {
         Self {
-            editor_state: EguiState::from_size(600, 800),
+            fft: Fft::default(),
+            history: [vec![0.0; MAX_FFT_SIZE], vec![0.0; MAX_FFT_SIZE]],
+            position: 0,
+            since_last_frame: 0,
+            window: vec![0.0; MAX_FFT_SIZE],
+            window_size: 0,
+            window_kind: SpectrumWindow::Hann,
+            window_gain: 1.0,
+            real: vec![0.0; MAX_FFT_SIZE],
+            imag: vec![0.0; MAX_FFT_SIZE],
+            averaged: [[0.0; SPECTRUM_POINTS]; 4],
+            unaveraged: SpectrumFrame::default(),
+        }
+    }
+}
 
-            selected_fx: EnumParam::new("Selected Fx", Fx::Panning),
-            panning_mode: EnumParam::new("Panning Mode", PanningMode::Linear),
+impl SpectrumAnalyzer {
+    /// Adds a stereo sample pair. Returns a new frame once enough samples came in since the last one.
+    pub fn process(
+        &mut self,
+        left: f32,
+        right: f32,
+        sample_rate: f32,
+        settings: &SpectrumSettings,
+    ) -> Option<SpectrumFrame> {
+        self.history[0][self.position] = left;
+        self.history[1][self.position] = right;
+        self.position = (self.position + 1) % MAX_FFT_SIZE;
+        self.since_last_frame += 1;
+
+        let hop = (settings.fft_size / 2).min(SPECTRUM_MAX_HOP);
+        if self.since_last_frame < hop {
+            return None;
+        }
+        self.since_last_frame = 0;
+
+        if settings.fft_size != self.window_size || settings.window != self.window_kind {
+            self.window_size = settings.fft_size;
+            self.window_kind = settings.window;
+            for (idx, value) in self.window[..settings.fft_size].iter_mut().enumerate() {
+                *value = settings.window.value(idx, settings.fft_size);
+            }
+            // Scales a full scale sine to 0 dB regardless of the window
+            let window_sum: f32 = self.window[..settings.fft_size].iter().sum();
+            self.window_gain = 2.0 / window_sum;
+        }
+
+        let smoothing = if settings.averaging_ms > 0.0 {
+            (-(hop as f32) / (settings.averaging_ms / 1000.0 * sample_rate)).exp()
+        } else {
+            0.0
+        };
+
+        let mut frame = SpectrumFrame::default();
+        self.unaveraged = SpectrumFrame::default();
+        for &channel in settings.channels {
+            self.analyze(channel, settings.fft_size);
+
+            // `real` now holds the power of every bin
+            let bin_width = sample_rate / settings.fft_size as f32;
+            let nyquist_bin = settings.fft_size / 2;
+            for (point, ((averaged, level), unaveraged)) in self.averaged[channel]
+                .iter_mut()
+                .zip(frame.levels[channel].iter_mut())
+                .zip(self.unaveraged.levels[channel].iter_mut())
+                .enumerate()
+            {
+                let low = spectrum_point_frequency(point as f32 - 0.5) / bin_width;
+                let high = spectrum_point_frequency(point as f32 + 0.5) / bin_width;
+                let center = spectrum_point_frequency(point as f32) / bin_width;
+                if center >= nyquist_bin as f32 {
+                    *averaged = 0.0;
+                    continue;
+                }
+
+                // Wide points show the loudest bin they cover, narrow ones interpolate
+                let first = low.ceil() as usize;
+                let last = (high.floor() as usize).min(nyquist_bin);
+                let power = if last >= first {
+                    self.real[first..=last].iter().copied().fold(0.0, f32::max)
+                } else {
+                    let bin = center.floor() as usize;
+                    lerp(self.real[bin], self.real[bin + 1], center.fract())
+                };
+
+                *averaged = smoothing * *averaged + (1.0 - smoothing) * power;
+                *level = 10.0 * (*averaged).max(1e-20).log10();
+                *unaveraged = 10.0 * power.max(1e-20).log10();
+            }
+        }
+
+        Some(frame)
+    }
+
+    /// The frame [`process()`][Self::process()] returned last, but without the averaging. The
+    /// spectrogram scrolls through these, since averaging would smear it out over time.
+    pub fn unaveraged(&self) -> &SpectrumFrame {
+        &self.unaveraged
+    }
+
+    /// Runs the FFT over the last `fft_size` samples of a signal, and leaves the power of every
+    /// bin up to Nyquist in `real`. Channels 2 and 3 are mid and side.
+    fn analyze(&mut self, channel: usize, fft_size: usize) {
+        let start = (self.position + MAX_FFT_SIZE - fft_size) % MAX_FFT_SIZE;
+        for idx in 0..fft_size {
+            let history_idx = (start + idx) % MAX_FFT_SIZE;
+            let left = self.history[0][history_idx];
+            let right = self.history[1][history_idx];
+            let sample = match channel {
+                0 => left,
+                1 => right,
+                2 => (left + right) * 0.5,
+                _ => (left - right) * 0.5,
+            };
+
+            self.real[idx] = sample * self.window[idx];
+            self.imag[idx] = 0.0;
+        }
+
+        self.fft
+            .process(&mut self.real[..fft_size], &mut self.imag[..fft_size]);
+
+        for idx in 0..=fft_size / 2 {
+            let magnitude = (self.real[idx] * self.real[idx] + self.imag[idx] * self.imag[idx])
+                .sqrt()
+                * self.window_gain;
+            self.real[idx] = magnitude * magnitude;
+        }
+    }
+}
+
+/// Running averages of the channel products, integrated with a one pole lowpass so the meters
+/// settle within roughly the integration time.
+#[derive(Default)]
+pub struct StereoMeter {
+    coefficient: f32,
+    left_right: f32,
+    left_left: f32,
+    right_right: f32,
+}
+
+impl StereoMeter {
+    pub fn set_integration_time(&mut self, sample_rate: f32, integration_ms: f32) {
+        self.coefficient = 1.0 - (-1000.0 / (integration_ms * sample_rate)).exp();
+    }
+
+    pub fn process(&mut self, left: f32, right: f32) {
+        self.left_right += (left * right - self.left_right) * self.coefficient;
+        self.left_left += (left * left - self.left_left) * self.coefficient;
+        self.right_right += (right * right - self.right_right) * self.coefficient;
+    }
+
+    pub fn readout(&self) -> StereoMeterReadout {
+        // Below roughly -120 dBFS both meters rest in the middle instead of amplifying noise
+        const SILENCE: f32 = 1e-12;
+
+        let energy = self.left_left + self.right_right;
+        if energy < SILENCE {
+            return StereoMeterReadout::default();
+        }
+
+        let correlation = if self.left_left * self.right_right < SILENCE * SILENCE {
+            0.0
+        } else {
+            self.left_right / (self.left_left * self.right_right).sqrt()
+        };
+
+        StereoMeterReadout {
+            correlation: correlation.clamp(-1.0, 1.0),
+            balance: ((self.right_right - self.left_left) / energy).clamp(-1.0, 1.0),
+        }
+    }
+}
+
+// Loudness is measured in 100 ms blocks, the momentary window spans 400 ms and the short-term
+// window 3 s, see ITU-R BS.1770 and EBU Tech 3341
+const LOUDNESS_BLOCK_SECONDS: f32 = 0.1;
+const MOMENTARY_BLOCKS: usize = 4;
+const SHORT_TERM_BLOCKS: usize = 30;
+const LOUDNESS_ABSOLUTE_GATE: f64 = -70.0;
+const INTEGRATED_RELATIVE_GATE: f64 = -10.0;
+const RANGE_RELATIVE_GATE: f64 = -20.0;
+// Gated loudness values are binned in 0.1 LU steps between the absolute gate and +30 LUFS, which
+// keeps integrated loudness and loudness range measurable over any length without allocating
+const LOUDNESS_HISTOGRAM_BINS: usize = 1000;
+const LOUDNESS_HISTOGRAM_STEP: f64 = 0.1;
+
+// 4x oversampling interpolation filter from ITU-R BS.1770 Annex 2, one row of taps per phase
+const TRUE_PEAK_TAPS: usize = 12;
+const TRUE_PEAK_PHASES: [[f32; TRUE_PEAK_TAPS]; 4] = [
+    [
+        0.001_708_984_4,
+        0.010_986_328,
+        -0.019_653_32,
+        0.033_203_125,
+        -0.059_448_242,
+        0.137_329_1,
+        0.972_167_97,
+        -0.102_294_92,
+        0.047_607_42,
+        -0.026_611_328,
+        0.014_892_578,
+        -0.008_300_781,
+    ],
+    [
+        -0.029_174_805,
+        0.029_296_875,
+        -0.051_757_81,
+        0.089_111_33,
+        -0.166_503_9,
+        0.465_087_9,
+        0.779_785_16,
+        -0.200_317_38,
+        0.101_562_5,
+        -0.058_227_54,
+        0.033_081_055,
+        -0.018_920_898,
+    ],
+    [
+        -0.018_920_898,
+        0.033_081_055,
+        -0.058_227_54,
+        0.101_562_5,
+        -0.200_317_38,
+        0.779_785_16,
+        0.465_087_9,
+        -0.166_503_9,
+        0.089_111_33,
+        -0.051_757_81,
+        0.029_296_875,
+        -0.029_174_805,
+    ],
+    [
+        -0.008_300_781,
+        0.014_892_578,
+        -0.026_611_328,
+        0.047_607_42,
+        -0.102_294_92,
+        0.972_167_97,
+        0.137_329_1,
+        -0.059_448_242,
+        0.033_203_125,
+        -0.019_653_32,
+        0.010_986_328,
+        0.001_708_984_4,
+    ],
+];
+
+/// Loudness in LUFS of a mean square energy summed over the channels.
+fn energy_to_lufs(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+/// What the loudness meter shows, sent to the editor every 100 ms. Values that haven't been
+/// measured yet are negative infinity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoudnessReadout {
+    /// Loudness over the last 400 ms in LUFS.
+    pub momentary: f32,
+    /// Loudness over the last 3 s in LUFS.
+    pub short_term: f32,
+    /// Gated loudness since the last reset in LUFS.
+    pub integrated: f32,
+    /// Loudness range since the last reset in LU.
+    pub range: f32,
+    /// Highest true peak since the last reset in dBTP.
+    pub true_peak: f32,
+}
+
+impl Default for LoudnessReadout {
+    fn default() -> Self {
+        Self {
+            momentary: f32::NEG_INFINITY,
+            short_term: f32::NEG_INFINITY,
+            integrated: f32::NEG_INFINITY,
+            range: 0.0,
+            true_peak: f32::NEG_INFINITY,
+        }
+    }
+}
+
+/// The two stage K-weighting prefilter, a high shelf modelling the head followed by the revised
+/// low frequency B-curve highpass. The analog prototypes are re-derived for every sample rate
+/// rather than using the 48 kHz coefficients from the spec.
+#[derive(Clone, Copy, Default)]
+pub struct KWeighting {
+    shelf: Biquad,
+    highpass: Biquad,
+}
+
+impl KWeighting {
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        let sample_rate = sample_rate as f64;
+
+        let k = (std::f64::consts::PI * 1_681.974_450_955_533 / sample_rate).tan();
+        let q = 0.707_175_236_955_419_6;
+        let high_gain = 10.0_f64.powf(3.999_843_853_973_347 / 20.0);
+        let band_gain = high_gain.powf(0.499_666_774_154_541_6);
+        let a0 = 1.0 + k / q + k * k;
+        self.shelf.b0 = ((high_gain + band_gain * k / q + k * k) / a0) as f32;
+        self.shelf.b1 = (2.0 * (k * k - high_gain) / a0) as f32;
+        self.shelf.b2 = ((high_gain - band_gain * k / q + k * k) / a0) as f32;
+        self.shelf.a1 = (2.0 * (k * k - 1.0) / a0) as f32;
+        self.shelf.a2 = ((1.0 - k / q + k * k) / a0) as f32;
+
+        let k = (std::f64::consts::PI * 38.135_470_876_024_44 / sample_rate).tan();
+        let q = 0.500_327_037_323_877_3;
+        let a0 = 1.0 + k / q + k * k;
+        self.highpass.b0 = 1.0;
+        self.highpass.b1 = -2.0;
+        self.highpass.b2 = 1.0;
+        self.highpass.a1 = (2.0 * (k * k - 1.0) / a0) as f32;
+        self.highpass.a2 = ((1.0 - k / q + k * k) / a0) as f32;
+
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.shelf.reset();
+        self.highpass.reset();
+    }
+
+    pub fn process(&mut self, sample: f32) -> f32 {
+        self.highpass.process(self.shelf.process(sample))
+    }
+}
+
+/// Per channel state of the loudness meter.
+#[derive(Clone, Copy, Default)]
+struct LoudnessChannel {
+    k_weighting: KWeighting,
+    // Every sample is written twice so the interpolation filter can always read a contiguous run
+    true_peak_history: [f32; TRUE_PEAK_TAPS * 2],
+    true_peak_index: usize,
+}
+
+impl LoudnessChannel {
+    fn reset(&mut self) {
+        self.k_weighting.reset();
+        self.true_peak_history = [0.0; TRUE_PEAK_TAPS * 2];
+        self.true_peak_index = 0;
+    }
+
+    /// Returns the K-weighted sample and the highest absolute value among the input sample and
+    /// the three interpolated points leading up to it.
+    fn process(&mut self, sample: f32) -> (f32, f32) {
+        self.true_peak_index = (self.true_peak_index + TRUE_PEAK_TAPS - 1) % TRUE_PEAK_TAPS;
+        self.true_peak_history[self.true_peak_index] = sample;
+        self.true_peak_history[self.true_peak_index + TRUE_PEAK_TAPS] = sample;
+
+        let history =
+            &self.true_peak_history[self.true_peak_index..self.true_peak_index + TRUE_PEAK_TAPS];
+        let peak = TRUE_PEAK_PHASES.iter().fold(sample.abs(), |peak, taps| {
+            let interpolated: f32 = taps.iter().zip(history).map(|(tap, x)| tap * x).sum();
+            peak.max(interpolated.abs())
+        });
+
+        (self.k_weighting.process(sample), peak)
+    }
+}
+
+/// Gated loudness blocks binned by their loudness, keeping the summed energy of every bin so the
+/// gated average stays exact apart from where the relative gate cuts through a bin.
+#[derive(Clone)]
+pub struct LoudnessHistogram {
+    counts: [u32; LOUDNESS_HISTOGRAM_BINS],
+    energies: [f64; LOUDNESS_HISTOGRAM_BINS],
+}
+
+impl Default for LoudnessHistogram {
+    fn default() -> Self {
+        Self {
+            counts: [0; LOUDNESS_HISTOGRAM_BINS],
+            energies: [0.0; LOUDNESS_HISTOGRAM_BINS],
+        }
+    }
+}
+
+impl LoudnessHistogram {
+    fn bin(lufs: f64) -> usize {
+        (((lufs - LOUDNESS_ABSOLUTE_GATE) / LOUDNESS_HISTOGRAM_STEP).max(0.0) as usize)
+            .min(LOUDNESS_HISTOGRAM_BINS - 1)
+    }
+
+    fn bin_lufs(bin: usize) -> f64 {
+        LOUDNESS_ABSOLUTE_GATE + (bin as f64 + 0.5) * LOUDNESS_HISTOGRAM_STEP
+    }
+
+    /// Adds a block, dropping it if it doesn't pass the absolute gate.
+    pub fn add(&mut self, energy: f64) {
+        let lufs = energy_to_lufs(energy);
+        if lufs > LOUDNESS_ABSOLUTE_GATE {
+            let bin = Self::bin(lufs);
+            self.counts[bin] += 1;
+            self.energies[bin] += energy;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.counts = [0; LOUDNESS_HISTOGRAM_BINS];
+        self.energies = [0.0; LOUDNESS_HISTOGRAM_BINS];
+    }
+
+    /// The first bin above the relative gate, or `None` when no block passed the absolute gate.
+    fn relative_gate_bin(&self, relative_gate: f64) -> Option<usize> {
+        let count: u64 = self.counts.iter().map(|&count| count as u64).sum();
+        if count == 0 {
+            return None;
+        }
+        let energy: f64 = self.energies.iter().sum();
+
+        Some(Self::bin(
+            energy_to_lufs(energy / count as f64) + relative_gate,
+        ))
+    }
+
+    /// Integrated loudness in LUFS following the two gates of ITU-R BS.1770.
+    pub fn integrated(&self) -> f64 {
+        let Some(gate_bin) = self.relative_gate_bin(INTEGRATED_RELATIVE_GATE) else {
+            return f64::NEG_INFINITY;
+        };
+
+        let count: u64 = self.counts[gate_bin..]
+            .iter()
+            .map(|&count| count as u64)
+            .sum();
+        let energy: f64 = self.energies[gate_bin..].iter().sum();
+
+        energy_to_lufs(energy / count as f64)
+    }
+
+    /// Loudness range in LU following EBU Tech 3342, the spread between the 10th and 95th
+    /// percentile of the gated short-term loudness.
+    pub fn range(&self) -> f64 {
+        let Some(gate_bin) = self.relative_gate_bin(RANGE_RELATIVE_GATE) else {
+            return 0.0;
+        };
+
+        let counts = &self.counts[gate_bin..];
+        let count: u64 = counts.iter().map(|&count| count as u64).sum();
+        let percentile = |fraction: f64| {
+            let target = ((count - 1) as f64 * fraction).round() as u64;
+            let mut seen = 0;
+            for (bin, &bin_count) in counts.iter().enumerate() {
+                seen += bin_count as u64;
+                if seen > target {
+                    return Self::bin_lufs(gate_bin + bin);
+                }
+            }
+            Self::bin_lufs(LOUDNESS_HISTOGRAM_BINS - 1)
+        };
+
+        percentile(0.95) - percentile(0.10)
+    }
+}
+
+/// Momentary, short-term and integrated loudness, loudness range and true peak following
+/// ITU-R BS.1770 and EBU R128.
+pub struct LoudnessMeter {
+    channels: [LoudnessChannel; 2],
+    block_length: usize,
+    block_position: usize,
+    block_energy: f64,
+    // Mean square energy of the most recent 100 ms blocks
+    blocks: [f64; SHORT_TERM_BLOCKS],
+    block_index: usize,
+    blocks_measured: usize,
+    integrated: LoudnessHistogram,
+    range: LoudnessHistogram,
+    true_peak: f32,
+    readout: LoudnessReadout,
+}
+
+impl Default for LoudnessMeter {
+    fn default() -> Self {
+        let mut meter = Self {
+            channels: [LoudnessChannel::default(); 2],
+            block_length: 0,
+            block_position: 0,
+            block_energy: 0.0,
+            blocks: [0.0; SHORT_TERM_BLOCKS],
+            block_index: 0,
+            blocks_measured: 0,
+            integrated: LoudnessHistogram::default(),
+            range: LoudnessHistogram::default(),
+            true_peak: 0.0,
+            readout: LoudnessReadout::default(),
+        };
+        meter.set_sample_rate(44100.0);
+        meter
+    }
+}
+
+impl LoudnessMeter {
+    /// Sets up the filters for a new sample rate and starts a fresh measurement.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        for channel in self.channels.iter_mut() {
+            channel.k_weighting.set_sample_rate(sample_rate);
+        }
+        self.block_length = (sample_rate * LOUDNESS_BLOCK_SECONDS).round() as usize;
+        self.reset();
+    }
+
+    /// Starts a fresh measurement. The filters are cleared too, otherwise whatever played right
+    /// before the reset would still show up in the first blocks and the true peak.
+    pub fn reset(&mut self) {
+        for channel in self.channels.iter_mut() {
+            channel.reset();
+        }
+        self.block_position = 0;
+        self.block_energy = 0.0;
+        self.blocks = [0.0; SHORT_TERM_BLOCKS];
+        self.block_index = 0;
+        self.blocks_measured = 0;
+        self.integrated.clear();
+        self.range.clear();
+        self.true_peak = 0.0;
+        self.readout = LoudnessReadout::default();
+    }
+
+    /// Measures one stereo sample, pass `None` as the right channel for mono. Returns a fresh
+    /// readout whenever a 100 ms block completes.
+    pub fn process(&mut self, left: f32, right: Option<f32>) -> Option<LoudnessReadout> {
+        let (weighted, peak) = self.channels[0].process(left);
+        self.block_energy += (weighted * weighted) as f64;
+        self.true_peak = self.true_peak.max(peak);
+
+        if let Some(right) = right {
+            let (weighted, peak) = self.channels[1].process(right);
+            self.block_energy += (weighted * weighted) as f64;
+            self.true_peak = self.true_peak.max(peak);
+        }
+
+        self.block_position += 1;
+        if self.block_position < self.block_length {
+            return None;
+        }
+
+        self.blocks[self.block_index] = self.block_energy / self.block_length as f64;
+        self.block_index = (self.block_index + 1) % SHORT_TERM_BLOCKS;
+        self.blocks_measured += 1;
+        self.block_position = 0;
+        self.block_energy = 0.0;
+
+        // Both windows slide by one block, so the gating blocks overlap by 75% and the short-term
+        // values feeding the loudness range are taken at 10 Hz
+        if self.blocks_measured >= MOMENTARY_BLOCKS {
+            let momentary = self.window_energy(MOMENTARY_BLOCKS);
+            self.integrated.add(momentary);
+            self.readout.momentary = energy_to_lufs(momentary) as f32;
+            self.readout.integrated = self.integrated.integrated() as f32;
+        }
+        if self.blocks_measured >= SHORT_TERM_BLOCKS {
+            let short_term = self.window_energy(SHORT_TERM_BLOCKS);
+            self.range.add(short_term);
+            self.readout.short_term = energy_to_lufs(short_term) as f32;
+            self.readout.range = self.range.range() as f32;
+        }
+        self.readout.true_peak = util::gain_to_db(self.true_peak);
+
+        Some(self.readout)
+    }
+
+    /// Mean energy of the most recent `block_count` blocks.
+    fn window_energy(&self, block_count: usize) -> f64 {
+        (1..=block_count)
+            .map(|age| {
+                self.blocks[(self.block_index + SHORT_TERM_BLOCKS - age) % SHORT_TERM_BLOCKS]
+            })
+            .sum::<f64>()
+            / block_count as f64
+    }
+}
+
+// Halfband lowpass used by every 2x oversampling stage. The length must be 4k + 1 so the center tap
+// lands on an even index, which leaves the even polyphase branch with just the center tap. The
+// length minus one is also a multiple of 16 so every stage delays by a whole number of host samples.
+const HALFBAND_TAPS: usize = 65;
+const HALFBAND_CENTER: usize = (HALFBAND_TAPS - 1) / 2;
+const HALFBAND_BRANCH_TAPS: usize = (HALFBAND_TAPS - 1) / 2;
+const MAX_OVERSAMPLING_STAGES: usize = 4;
+const MAX_OVERSAMPLING_RATIO: usize = 1 << MAX_OVERSAMPLING_STAGES;
+
+/// Zeroth order modified Bessel function, only needed for the Kaiser window.
+fn bessel_i0(x: f32) -> f32 {
+    let half_x = x / 2.0;
+    let mut sum = 1.0;
+    let mut term = 1.0;
+
+    for k in 1..32 {
+        let factor = half_x / k as f32;
+        term *= factor * factor;
+        sum += term;
+    }
+
+    sum
+}
+
+/// Kaiser windowed sinc halfband filter. Only the odd taps are stored since the even taps are all
+/// zero except for the center tap, which is always 0.5.
+fn halfband_coefficients() -> [f32; HALFBAND_BRANCH_TAPS] {
+    let beta = 8.0;
+    let mut coefficients = [0.0; HALFBAND_BRANCH_TAPS];
+
+    for (branch_idx, coefficient) in coefficients.iter_mut().enumerate() {
+        let tap = 2 * branch_idx + 1;
+        let offset = tap as f32 - HALFBAND_CENTER as f32;
+        let x = std::f32::consts::PI * offset * 0.5;
+        let sinc = x.sin() / x;
+        let position = 2.0 * tap as f32 / (HALFBAND_TAPS - 1) as f32 - 1.0;
+        let window = bessel_i0(beta * (1.0 - position * position).sqrt()) / bessel_i0(beta);
+
+        *coefficient = 0.5 * sinc * window;
+    }
+
+    // Normalize so the full filter has unity gain at DC. The center tap contributes the other half.
+    let sum: f32 = coefficients.iter().sum();
+    for coefficient in coefficients.iter_mut() {
+        *coefficient *= 0.5 / sum;
+    }
+
+    coefficients
+}
+
+/// Delay line for one polyphase branch. Every sample is written twice so the most recent
+/// `HALFBAND_BRANCH_TAPS` samples can always be read back as one contiguous slice, newest first.
+#[derive(Clone, Copy)]
+pub struct HalfbandHistory {
+    samples: [f32; 2 * HALFBAND_BRANCH_TAPS],
+    position: usize,
+}
+
+impl HalfbandHistory {
+    pub fn push(&mut self, sample: f32) {
+        self.position = if self.position == 0 {
+            HALFBAND_BRANCH_TAPS - 1
+        } else {
+            self.position - 1
+        };
+
+        self.samples[self.position] = sample;
+        self.samples[self.position + HALFBAND_BRANCH_TAPS] = sample;
+    }
+
+    pub fn taps(&self) -> &[f32] {
+        &self.samples[self.position..self.position + HALFBAND_BRANCH_TAPS]
+    }
+}
+
+impl Default for HalfbandHistory {
+    fn default() -> Self {
+        Self {
+            samples: [0.0; 2 * HALFBAND_BRANCH_TAPS],
+            position: 0,
+        }
+    }
+}
+
+/// Polyphase halfband oversampler for a single channel. Every stage doubles the rate, so up to 16x
+/// is supported. Each stage keeps its own state for the upsampling and downsampling filters.
+pub struct Oversampler {
+    coefficients: [f32; HALFBAND_BRANCH_TAPS],
+    upsamplers: [HalfbandHistory; MAX_OVERSAMPLING_STAGES],
+    downsamplers_even: [HalfbandHistory; MAX_OVERSAMPLING_STAGES],
+    downsamplers_odd: [HalfbandHistory; MAX_OVERSAMPLING_STAGES],
+}
+
+impl Oversampler {
+    /// Latency in samples at the host rate. Each stage delays by `HALFBAND_TAPS - 1` samples at
+    /// its own rate (half for upsampling and half for downsampling).
+    pub fn latency(stages: usize) -> u32 {
+        (1..=stages.min(MAX_OVERSAMPLING_STAGES))
+            .map(|stage| ((HALFBAND_TAPS - 1) >> stage) as u32)
+            .sum()
+    }
+
+    pub fn reset(&mut self) {
+        self.upsamplers = [HalfbandHistory::default(); MAX_OVERSAMPLING_STAGES];
+        self.downsamplers_even = [HalfbandHistory::default(); MAX_OVERSAMPLING_STAGES];
+        self.downsamplers_odd = [HalfbandHistory::default(); MAX_OVERSAMPLING_STAGES];
+    }
+
+    /// Upsample a single sample by `2^stages`, run every upsampled sample through `shaper`, and
+    /// filter the result back down to the host rate.
+    pub fn process(
+        &mut self,
+        input: f32,
+        stages: usize,
+        mut shaper: impl FnMut(f32) -> f32,
+    ) -> f32 {
+        if stages == 0 {
+            return shaper(input);
+        }
+
+        let stages = stages.min(MAX_OVERSAMPLING_STAGES);
+        let mut current = [0.0; MAX_OVERSAMPLING_RATIO];
+        let mut next = [0.0; MAX_OVERSAMPLING_RATIO];
+        let mut len = 1;
+        current[0] = input;
+
+        for stage in 0..stages {
+            let history = &mut self.upsamplers[stage];
+
+            for sample_idx in 0..len {
+                history.push(current[sample_idx]);
+                let taps = history.taps();
+
+                // Zero stuffing halves the signal level, so both branches get a gain of 2. The even
+                // branch only has the 0.5 center tap, which turns into a plain delay.
+                next[2 * sample_idx] = taps[HALFBAND_CENTER / 2];
+                next[2 * sample_idx + 1] = 2.0 * dot(&self.coefficients, taps);
+            }
+
+            len *= 2;
+            std::mem::swap(&mut current, &mut next);
+        }
+
+        for sample in current[..len].iter_mut() {
+            *sample = shaper(*sample);
+        }
+
+        for stage in (0..stages).rev() {
+            len /= 2;
+
+            for sample_idx in 0..len {
+                let even = &mut self.downsamplers_even[stage];
+                let odd = &mut self.downsamplers_odd[stage];
+                even.push(current[2 * sample_idx]);
+
+                // The odd branch only needs the previous odd samples, so it's read before pushing
+                let filtered =
+                    0.5 * even.taps()[HALFBAND_CENTER / 2] + dot(&self.coefficients, odd.taps());
+                odd.push(current[2 * sample_idx + 1]);
+
+                current[sample_idx] = filtered;
+            }
+        }
+
+        current[0]
+    }
+}
+
+impl Default for Oversampler {
+    fn default() -> Self {
+        Self {
+            coefficients: halfband_coefficients(),
+            upsamplers: [HalfbandHistory::default(); MAX_OVERSAMPLING_STAGES],
+            downsamplers_even: [HalfbandHistory::default(); MAX_OVERSAMPLING_STAGES],
+            downsamplers_odd: [HalfbandHistory::default(); MAX_OVERSAMPLING_STAGES],
+        }
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Runs the selected effect on mid and side instead of left and right. The paths get their own
+/// mix and level, so an effect can for instance be kept off the side while the mid goes through.
+/// The effects themselves read their per path settings from [`MidSidePathParams`].
+pub struct MidSideWrapper {
+    dry: [Vec<f32>; 2],
+    dry_delay: [SampleDelay; 2],
+}
+
+impl Default for MidSideWrapper {
+    fn default() -> Self {
+        Self {
+            dry: [Vec::new(), Vec::new()],
+            dry_delay: [SampleDelay::new(128), SampleDelay::new(128)],
+        }
+    }
+}
+
+impl MidSideWrapper {
+    pub fn initialize(&mut self, max_buffer_size: usize) {
+        for dry in self.dry.iter_mut() {
+            dry.resize(max_buffer_size, 0.0);
+        }
+    }
+
+    /// How many samples of a block fit in the dry buffers.
+    pub fn block_size(&self, num_samples: usize) -> usize {
+        num_samples.min(self.dry[0].len())
+    }
+
+    /// Turns the first two channels into mid and side, keeping a copy of them for the dry paths.
+    pub fn encode(&mut self, channels: &mut [&mut [f32]], num_samples: usize) {
+        let (left, right) = channels.split_at_mut(1);
+        let [dry_mid, dry_side] = &mut self.dry;
+
+        for (((left, right), dry_mid), dry_side) in left[0]
+            .iter_mut()
+            .zip(right[0].iter_mut())
+            .zip(dry_mid.iter_mut())
+            .zip(dry_side.iter_mut())
+            .take(num_samples)
+        {
+            let (mid, side) = mid_side_encode(*left, *right, 1.0);
+            *left = mid;
+            *right = side;
+            *dry_mid = mid;
+            *dry_side = side;
+        }
+    }
+
+    /// Mixes the processed mid and side with their dry copies and goes back to left and right.
+    /// The dry paths are delayed by `latency` to line up with the effect's output.
+    pub fn decode(
+        &mut self,
+        channels: &mut [&mut [f32]],
+        num_samples: usize,
+        latency: usize,
+        mix: [f32; 2],
+        gain: [f32; 2],
+    ) {
+        for (((channel, dry), dry_delay), (mix, gain)) in channels
+            .iter_mut()
+            .zip(self.dry.iter())
+            .zip(self.dry_delay.iter_mut())
+            .zip(mix.into_iter().zip(gain))
+        {
+            for (sample, dry) in channel.iter_mut().zip(dry.iter()).take(num_samples) {
+                let dry = dry_delay.process(*dry, latency);
+                *sample = (mix * *sample + (1.0 - mix) * dry) * gain;
+            }
+        }
+
+        let (mid, side) = channels.split_at_mut(1);
+        for (mid, side) in mid[0].iter_mut().zip(side[0].iter_mut()).take(num_samples) {
+            let (left, right) = mid_side_decode(*mid, *side);
+            *mid = left;
+            *side = right;
+        }
+    }
+}
+
+/// Short integer delay, used to keep a dry signal lined up with processing that adds latency.
+pub struct SampleDelay {
+    buffer: Vec<f32>,
+    position: usize,
+}
+
+impl SampleDelay {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: vec![0.0; capacity],
+            position: 0,
+        }
+    }
+
+    pub fn process(&mut self, input: f32, delay_samples: usize) -> f32 {
+        let capacity = self.buffer.len();
+        self.buffer[self.position] = input;
+        let output =
+            self.buffer[(self.position + capacity - delay_samples.min(capacity - 1)) % capacity];
+        self.position = (self.position + 1) % capacity;
+
+        output
+    }
+
+    /// Like [`SampleDelay::process`], but with a fractional delay that's linearly interpolated.
+    pub fn process_fractional(&mut self, input: f32, delay_samples: f32) -> f32 {
+        let capacity = self.buffer.len();
+        self.buffer[self.position] = input;
+
+        let delay_samples = delay_samples.clamp(0.0, (capacity - 2) as f32);
+        let whole = delay_samples as usize;
+        let newer = self.buffer[(self.position + capacity - whole) % capacity];
+        let older = self.buffer[(self.position + capacity - whole - 1) % capacity];
+        self.position = (self.position + 1) % capacity;
+
+        lerp(newer, older, delay_samples - whole as f32)
+    }
+}
+
+// Spherical head model after Brown and Duda, "A structural model for binaural sound synthesis"
+const HEAD_RADIUS: f32 = 0.0875;
+const SPEED_OF_SOUND: f32 = 343.0;
+// The head shadow is strongest a bit behind the ear rather than straight opposite of it
+const HEAD_SHADOW_MIN_ALPHA: f32 = 0.1;
+const HEAD_SHADOW_MIN_ANGLE: f32 = 5.0 * std::f32::consts::PI / 6.0;
+
+/// One ear of the binaural panner, a fractional delay for the time difference followed by the head
+/// shadow filter, which brings in the level difference that grows with frequency.
+struct BinauralEar {
+    delay: SampleDelay,
+    delay_samples: f32,
+    shadow: Biquad,
+}
+
+impl Default for BinauralEar {
+    fn default() -> Self {
+        Self {
+            delay: SampleDelay::new(256),
+            delay_samples: 0.0,
+            shadow: Biquad::default(),
+        }
+    }
+}
+
+impl BinauralEar {
+    /// `incidence` is the angle between the ear's axis and the direction of the source, 0 when
+    /// the source is right in front of the ear.
+    fn set_incidence(&mut self, sample_rate: f32, incidence: f32) {
+        let head_delay = HEAD_RADIUS / SPEED_OF_SOUND;
+
+        // Woodworth's formula, straight to the near side and around the sphere on the far side,
+        // offset so the ear facing the source isn't delayed at all
+        let delay = if incidence < std::f32::consts::FRAC_PI_2 {
+            -head_delay * incidence.cos()
+        } else {
+            head_delay * (incidence - std::f32::consts::FRAC_PI_2)
+        };
+        self.delay_samples = (delay + head_delay) * sample_rate;
+
+        // One pole, one zero shelf at c / a through the bilinear transform. Alpha goes from 2 (a
+        // 6 dB high boost) facing the source down to 0.1 in the shadow behind the head.
+        let alpha = (1.0 + HEAD_SHADOW_MIN_ALPHA / 2.0)
+            + (1.0 - HEAD_SHADOW_MIN_ALPHA / 2.0)
+                * (incidence / HEAD_SHADOW_MIN_ANGLE * std::f32::consts::PI).cos();
+        let k = sample_rate * HEAD_RADIUS / SPEED_OF_SOUND;
+        self.shadow.b0 = (1.0 + alpha * k) / (1.0 + k);
+        self.shadow.b1 = (1.0 - alpha * k) / (1.0 + k);
+        self.shadow.b2 = 0.0;
+        self.shadow.a1 = (1.0 - k) / (1.0 + k);
+        self.shadow.a2 = 0.0;
+    }
+
+    fn process(&mut self, sample: f32) -> f32 {
+        let delayed = self.delay.process_fractional(sample, self.delay_samples);
+        self.shadow.process(delayed)
+    }
+}
+
+/// Places a mono source around a listener on headphones, using interaural time and level
+/// differences from a spherical head instead of a measured HRTF set.
+#[derive(Default)]
+pub struct Binaural {
+    left: BinauralEar,
+    right: BinauralEar,
+}
+
+impl Binaural {
+    /// Azimuth goes clockwise from straight ahead, so 90 degrees is hard right, and elevation goes
+    /// up from the horizontal plane. Both in degrees.
+    pub fn set_position(&mut self, sample_rate: f32, azimuth: f32, elevation: f32) {
+        let (azimuth, elevation) = (azimuth.to_radians(), elevation.to_radians());
+
+        // Only how far the source is off to the side matters to a sphere with the ears on its
+        // axis, so elevation moves sources towards the center
+        let lateral = (azimuth.sin() * elevation.cos()).clamp(-1.0, 1.0);
+        self.left.set_incidence(sample_rate, (-lateral).acos());
+        self.right.set_incidence(sample_rate, lateral.acos());
+    }
+
+    pub fn process(&mut self, sample: f32) -> (f32, f32) {
+        (self.left.process(sample), self.right.process(sample))
+    }
+}
+
+// Below this input difference the ADAA quotients are ill-conditioned and the midpoint is used instead
+const ADAA_TOLERANCE: f64 = 1e-5;
+
+/// First and second antiderivatives of the static shapers that support antiderivative
+/// antialiasing. Evaluated in `f64` since the ADAA quotients subtract nearly equal values.
+pub fn distortion_antiderivatives(sample: f64, settings: &ShaperSettings) -> (f64, f64) {
+    let x = sample;
+    let distortion_amount = settings.amount as f64;
+    match settings.mode {
+        DistortionMode::InfiniteClipping => {
+            let a = distortion_amount;
+            (
+                (1.0 - a) * x * x / 2.0 + a * x.abs(),
+                (1.0 - a) * x * x * x / 6.0 + a * x * x.abs() / 2.0,
+            )
+        }
+        DistortionMode::Cubic => {
+            let a = distortion_amount;
+            (
+                x * x / 2.0 - a * x.powi(4) / 12.0,
+                x * x * x / 6.0 - a * x.powi(5) / 60.0,
+            )
+        }
+        DistortionMode::ArcTangent => {
+            let alpha = (distortion_amount * 10.0).max(1.0);
+            let scale = 2.0 / std::f64::consts::PI;
+            let atan = (alpha * x).atan();
+            let log = (1.0 + alpha * alpha * x * x).ln();
+            (
+                scale * (x * atan - log / (2.0 * alpha)),
+                scale
+                    * ((x * x / 2.0 - 1.0 / (2.0 * alpha * alpha)) * atan + x / (2.0 * alpha)
+                        - x * log / (2.0 * alpha)),
+            )
+        }
+        DistortionMode::ExponentialSoftClipping => {
+            let gain = (distortion_amount * 10.0).max(1.0);
+            let decay = (-gain * x.abs()).exp();
+            (
+                x.abs() + (decay - 1.0) / gain,
+                x.signum()
+                    * (x * x / 2.0 - x.abs() / gain - decay / (gain * gain) + 1.0 / (gain * gain)),
+            )
+        }
+        // The folders and rectifiers fade in with the amount, so the clean signal's
+        // antiderivatives are mixed in as well
+        DistortionMode::SineFold => {
+            let a = distortion_amount;
+            let k = std::f64::consts::FRAC_PI_2 * settings.fold_count as f64;
+            (
+                (1.0 - a) * x * x / 2.0 - a * (k * x).cos() / k,
+                (1.0 - a) * x * x * x / 6.0 - a * (k * x).sin() / (k * k),
+            )
+        }
+        DistortionMode::HalfWaveRectifier => {
+            let a = distortion_amount;
+            let positive = x.max(0.0);
+            (
+                (1.0 - a) * x * x / 2.0 + a * positive * positive / 2.0,
+                (1.0 - a) * x * x * x / 6.0 + a * positive * positive * positive / 6.0,
+            )
+        }
+        DistortionMode::FullWaveRectifier => {
+            let a = distortion_amount;
+            (
+                (1.0 - a) * x * x / 2.0 + a * x * x.abs() / 2.0,
+                (1.0 - a) * x * x * x / 6.0 + a * x.abs() * x.abs() * x.abs() / 6.0,
+            )
+        }
+        _ => (0.0, 0.0),
+    }
+}
+
+/// Antiderivative antialiasing state for one channel. The first order variant delays the signal by
+/// half a sample and the second order variant by a full sample.
+#[derive(Clone, Copy, Default)]
+pub struct Adaa {
+    x1: f64,
+    x2: f64,
+    d1: f64,
+}
+
+impl Adaa {
+    pub fn process(
+        &mut self,
+        sample: f32,
+        antialiasing: Antialiasing,
+        settings: &ShaperSettings,
+    ) -> f32 {
+        let shaper = |x: f64| distortion_shaper(x as f32, settings) as f64;
+        let antiderivatives = |x: f64| distortion_antiderivatives(x, settings);
+
+        let x = sample as f64;
+        let output = if !settings.mode.supports_adaa() {
+            shaper(x)
+        } else {
+            match antialiasing {
+                Antialiasing::Off => shaper(x),
+                Antialiasing::FirstOrder => {
+                    let dx = x - self.x1;
+                    if dx.abs() < ADAA_TOLERANCE {
+                        shaper((x + self.x1) / 2.0)
+                    } else {
+                        (antiderivatives(x).0 - antiderivatives(self.x1).0) / dx
+                    }
+                }
+                Antialiasing::SecondOrder => {
+                    let dx = x - self.x1;
+                    let d1 = if dx.abs() < ADAA_TOLERANCE {
+                        antiderivatives((x + self.x1) / 2.0).0
+                    } else {
+                        (antiderivatives(x).1 - antiderivatives(self.x1).1) / dx
+                    };
+
+                    let output = if (x - self.x2).abs() < ADAA_TOLERANCE {
+                        let x_bar = (x + self.x2) / 2.0;
+                        let delta = x_bar - self.x1;
+                        if delta.abs() < ADAA_TOLERANCE {
+                            shaper((x_bar + self.x1) / 2.0)
+                        } else {
+                            (2.0 / delta)
+                                * (antiderivatives(x_bar).0
+                                    + (antiderivatives(self.x1).1 - antiderivatives(x_bar).1)
+                                        / delta)
+                        }
+                    } else {
+                        2.0 * (d1 - self.d1) / (x - self.x2)
+                    };
+
+                    self.d1 = d1;
+                    output
+                }
+            }
+        };
+
+        self.x2 = self.x1;
+        self.x1 = x;
+
+        output as f32
+    }
+}
+
+// Resolution of the baked custom curve, one extra entry so the last segment ends exactly at +1
+const TRANSFER_CURVE_SIZE: usize = 1024;
+
+/// Default points for the custom curve, a gentle symmetric soft clipper
+pub fn default_custom_curve() -> Vec<(f32, f32)> {
+    vec![
+        (-1.0, -1.0),
+        (-0.5, -0.75),
+        (0.0, 0.0),
+        (0.5, 0.75),
+        (1.0, 1.0),
+    ]
+}
+
+/// Replaces curve points that the editor couldn't have produced, e.g. from a damaged preset, with
+/// the default curve. Returns whether the points had to be replaced.
+pub fn sanitize_custom_curve(points: &mut Vec<(f32, f32)>) -> bool {
+    let is_valid = points.len() >= 2
+        && points.first().is_some_and(|&(x, _)| x == -1.0)
+        && points.last().is_some_and(|&(x, _)| x == 1.0)
+        && points.windows(2).all(|pair| pair[0].0 < pair[1].0)
+        && points.iter().all(|&(_, y)| (-1.0..=1.0).contains(&y));
+
+    if !is_valid {
+        *points = default_custom_curve();
+    }
+
+    !is_valid
+}
+
+/// Evaluates the user drawn curve at `x`. The points must be sorted by their x coordinate and are
+/// connected with a cubic Hermite spline using Catmull-Rom style tangents, so the curve passes
+/// through every point and can go up and down freely.
+pub fn custom_curve_spline(points: &[(f32, f32)], x: f32) -> f32 {
+    match points.len() {
+        0 => return x,
+        1 => return points[0].1,
+        _ => {}
+    }
+
+    let last = points.len() - 1;
+    let x = x.clamp(points[0].0, points[last].0);
+    let segment = points
+        .windows(2)
+        .position(|pair| x <= pair[1].0)
+        .unwrap_or(last - 1);
+    let (x0, y0) = points[segment];
+    let (x1, y1) = points[segment + 1];
+    let width = x1 - x0;
+    if width <= f32::EPSILON {
+        return y1;
+    }
+
+    let tangent = |idx: usize| {
+        let (prev_x, prev_y) = points[idx.saturating_sub(1)];
+        let (next_x, next_y) = points[(idx + 1).min(last)];
+        (next_y - prev_y) / (next_x - prev_x).max(f32::EPSILON)
+    };
+    let m0 = tangent(segment) * width;
+    let m1 = tangent(segment + 1) * width;
+
+    let t = (x - x0) / width;
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+        + (t3 - 2.0 * t2 + t) * m0
+        + (-2.0 * t3 + 3.0 * t2) * y1
+        + (t3 - t2) * m1
+}
+
+/// The custom curve baked into a lookup table over `[-1, 1]`, so the audio thread never has to
+/// touch the spline points or the lock guarding them while processing.
+pub struct TransferCurve {
+    table: [f32; TRANSFER_CURVE_SIZE + 1],
+}
+
+impl Default for TransferCurve {
+    fn default() -> Self {
+        let mut curve = Self {
+            table: [0.0; TRANSFER_CURVE_SIZE + 1],
+        };
+        curve.bake(&default_custom_curve());
+        curve
+    }
+}
+
+impl TransferCurve {
+    /// Doesn't allocate, so this can be called from the audio thread.
+    pub fn bake(&mut self, points: &[(f32, f32)]) {
+        for (idx, value) in self.table.iter_mut().enumerate() {
+            let x = (idx as f32 / TRANSFER_CURVE_SIZE as f32) * 2.0 - 1.0;
+            *value = custom_curve_spline(points, x).clamp(-1.0, 1.0);
+        }
+    }
+
+    /// Linearly interpolates the table. Inputs outside of `[-1, 1]` are clamped.
+    pub fn lookup(&self, sample: f32) -> f32 {
+        let position = ((sample.clamp(-1.0, 1.0) + 1.0) / 2.0) * TRANSFER_CURVE_SIZE as f32;
+        let idx = (position as usize).min(TRANSFER_CURVE_SIZE - 1);
+        let frac = position - idx as f32;
+
+        lerp(self.table[idx], self.table[idx + 1], frac)
+    }
+}
+
+// Frequency the tilt filters pivot around, the gain here is always 0 dB
+const TILT_PIVOT_HZ: f32 = 700.0;
+
+/// First order tilt equalizer. A tilt of `x` dB boosts the highs by `x / 2` dB and cuts the lows by
+/// the same amount, and a tilt of `-x` dB is its exact inverse. That lets the distortion emphasize
+/// part of the spectrum before the shaper and undo it afterwards.
+#[derive(Clone, Copy)]
+pub struct TiltFilter {
+    b0: f32,
+    b1: f32,
+    a1: f32,
+    z1: f32,
+}
+
+impl TiltFilter {
+    pub fn set_tilt(&mut self, sample_rate: f32, tilt_db: f32) {
+        let high_gain = db_to_gain(tilt_db / 2.0);
+        let pivot = 2.0 * std::f32::consts::PI * TILT_PIVOT_HZ;
+
+        // Analog prototype `high_gain * (s + zero) / (s + pole)`, prewarped at the pivot
+        let k = pivot / (pivot / (2.0 * sample_rate)).tan();
+        let zero = pivot / high_gain;
+        let pole = pivot * high_gain;
+
+        self.b0 = high_gain * (k + zero) / (k + pole);
+        self.b1 = high_gain * (zero - k) / (k + pole);
+        self.a1 = (pole - k) / (k + pole);
+    }
+
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let output = self.b0 * sample + self.z1;
+        self.z1 = self.b1 * sample - self.a1 * output;
+
+        output
+    }
+}
+
+impl Default for TiltFilter {
+    fn default() -> Self {
+        Self {
+            b0: 1.0,
+            b1: 0.0,
+            a1: 0.0,
+            z1: 0.0,
+        }
+    }
+}
+
+/// Transposed direct form II biquad, coefficients from the RBJ audio EQ cookbook.
+#[derive(Clone, Copy)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    pub fn set_lowpass(&mut self, sample_rate: f32, frequency: f32, q: f32) {
+        let omega = 2.0 * std::f32::consts::PI * frequency / sample_rate;
+        let alpha = omega.sin() / (2.0 * q);
+        let cos = omega.cos();
+        let a0 = 1.0 + alpha;
+
+        self.b0 = (1.0 - cos) / 2.0 / a0;
+        self.b1 = (1.0 - cos) / a0;
+        self.b2 = (1.0 - cos) / 2.0 / a0;
+        self.a1 = -2.0 * cos / a0;
+        self.a2 = (1.0 - alpha) / a0;
+    }
+
+    pub fn set_highpass(&mut self, sample_rate: f32, frequency: f32, q: f32) {
+        let omega = 2.0 * std::f32::consts::PI * frequency / sample_rate;
+        let alpha = omega.sin() / (2.0 * q);
+        let cos = omega.cos();
+        let a0 = 1.0 + alpha;
+
+        self.b0 = (1.0 + cos) / 2.0 / a0;
+        self.b1 = -(1.0 + cos) / a0;
+        self.b2 = (1.0 + cos) / 2.0 / a0;
+        self.a1 = -2.0 * cos / a0;
+        self.a2 = (1.0 - alpha) / a0;
+    }
+
+    pub fn set_allpass(&mut self, sample_rate: f32, frequency: f32, q: f32) {
+        let omega = 2.0 * std::f32::consts::PI * frequency / sample_rate;
+        let alpha = omega.sin() / (2.0 * q);
+        let cos = omega.cos();
+        let a0 = 1.0 + alpha;
+
+        self.b0 = (1.0 - alpha) / a0;
+        self.b1 = -2.0 * cos / a0;
+        self.b2 = 1.0;
+        self.a1 = -2.0 * cos / a0;
+        self.a2 = (1.0 - alpha) / a0;
+    }
+
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let output = self.b0 * sample + self.z1;
+        self.z1 = self.b1 * sample - self.a1 * output + self.z2;
+        self.z2 = self.b2 * sample - self.a2 * output;
+
+        output
+    }
+
+    pub fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+}
+
+impl Default for Biquad {
+    fn default() -> Self {
+        Self {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+}
+
+// The top of the rate range turns rate reduction off, so hosts running above 48 kHz aren't
+// decimated unless asked to
+const BIT_CRUSH_RATE_OFF: f32 = 192_000.0;
+
+/// Sample and hold rate reducer for the bit crusher. Jitter randomizes the length of every hold
+/// period, and the optional prefilter is a 4th order Butterworth lowpass below the target rate.
+pub struct Decimator {
+    prefilter: [Biquad; 2],
+    phase: f32,
+    hold_length: f32,
+    held: f32,
+    rng_state: u32,
+}
+
+impl Decimator {
+    pub fn set_prefilter(&mut self, sample_rate: f32, target_rate: f32) {
+        let cutoff = (0.45 * target_rate).min(0.45 * sample_rate);
+        // Q values for the two sections of a 4th order Butterworth filter
+        self.prefilter[0].set_lowpass(sample_rate, cutoff, 0.541_196_1);
+        self.prefilter[1].set_lowpass(sample_rate, cutoff, 1.306_563);
+    }
+
+    pub fn process(
+        &mut self,
+        sample: f32,
+        sample_rate: f32,
+        target_rate: f32,
+        jitter: f32,
+        prefilter: bool,
+    ) -> f32 {
+        if target_rate >= sample_rate {
+            return sample;
+        }
+
+        let filtered = if prefilter {
+            let first_section = self.prefilter[0].process(sample);
+            self.prefilter[1].process(first_section)
+        } else {
+            sample
+        };
+
+        self.phase += target_rate / sample_rate;
+        if self.phase >= self.hold_length {
+            self.phase -= self.hold_length;
+            self.held = filtered;
+            self.hold_length = 1.0 + jitter * (self.next_random() - 0.5);
+        }
+
+        self.held
+    }
+
+    /// Xorshift, returns a value between 0 and 1.
+    fn next_random(&mut self) -> f32 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 17;
+        self.rng_state ^= self.rng_state << 5;
+
+        self.rng_state as f32 / u32::MAX as f32
+    }
+}
+
+impl Default for Decimator {
+    fn default() -> Self {
+        Self {
+            prefilter: [Biquad::default(); 2],
+            phase: 0.0,
+            hold_length: 1.0,
+            held: 0.0,
+            rng_state: 0x9E37_79B9,
+        }
+    }
+}
+
+// Low enough to leave the audible range alone while still settling quickly after a bias change
+const DC_BLOCKER_CUTOFF_HZ: f32 = 10.0;
+
+/// One pole, one zero highpass that removes the DC offset asymmetric shapers introduce.
+#[derive(Clone, Copy, Default)]
+pub struct DcBlocker {
+    pole: f32,
+    x1: f32,
+    y1: f32,
+}
+
+impl DcBlocker {
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.pole = (-2.0 * std::f32::consts::PI * DC_BLOCKER_CUTOFF_HZ / sample_rate).exp();
+    }
+
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let output = sample - self.x1 + self.pole * self.y1;
+        self.x1 = sample;
+        self.y1 = output;
+
+        output
+    }
+}
+
+/// Hysteresis style tape saturation. The magnetization is pulled towards the anhysteretic curve
+/// (the `Tape` shaper) by an amount proportional to how far the input moved, so it lags behind on
+/// the way up and on the way down. That gives the loop its width independent of the frequency.
+/// Record emphasis before and playback de-emphasis after make the highs saturate earlier.
+#[derive(Clone, Copy, Default)]
+pub struct Tape {
+    magnetization: f32,
+    previous_input: f32,
+    record_emphasis: TiltFilter,
+    playback_emphasis: TiltFilter,
+    playback_loss: Biquad,
+}
+
+impl Tape {
+    pub fn set_speed(&mut self, sample_rate: f32, tape_speed: TapeSpeed) {
+        let emphasis = tape_speed.emphasis_db();
+        self.record_emphasis.set_tilt(sample_rate, emphasis);
+        self.playback_emphasis.set_tilt(sample_rate, -emphasis);
+        self.playback_loss.set_lowpass(
+            sample_rate,
+            tape_speed.loss_frequency().min(sample_rate * 0.45),
+            std::f32::consts::FRAC_1_SQRT_2,
+        );
+    }
+
+    pub fn record(&mut self, sample: f32) -> f32 {
+        self.record_emphasis.process(sample)
+    }
+
+    /// `hysteresis` goes from 0 to 1 and sets the width of the loop.
+    pub fn magnetize(&mut self, sample: f32, hysteresis: f32, settings: &ShaperSettings) -> f32 {
+        let anhysteretic = distortion_shaper(sample, settings);
+        let width = 0.001 + 0.2 * hysteresis;
+        let rate = ((sample - self.previous_input).abs() / width).min(1.0);
+        self.magnetization += (anhysteretic - self.magnetization) * rate;
+        self.previous_input = sample;
+
+        self.magnetization
+    }
+
+    pub fn playback(&mut self, sample: f32) -> f32 {
+        self.playback_loss
+            .process(self.playback_emphasis.process(sample))
+    }
+}
+
+// Most bands the multiband distortion can split into, and the crossovers needed for that
+pub const MAX_DISTORTION_BANDS: usize = 4;
+const MAX_CROSSOVERS: usize = MAX_DISTORTION_BANDS - 1;
+
+/// 4th order Linkwitz-Riley crossover network. Every crossover is a pair of cascaded Butterworth
+/// biquads, so the lowpass and highpass outputs sum back to a flat allpass. The lower bands also
+/// go through the allpasses of the crossovers above them, which keeps every band in phase so the
+/// bands still sum flat after splitting more than once.
+#[derive(Default)]
+pub struct Crossover {
+    lowpass: [[Biquad; 2]; MAX_CROSSOVERS],
+    highpass: [[Biquad; 2]; MAX_CROSSOVERS],
+    allpass: [[Biquad; MAX_CROSSOVERS]; MAX_DISTORTION_BANDS],
+}
+
+impl Crossover {
+    /// `frequencies` must be sorted.
+    pub fn set_frequencies(&mut self, sample_rate: f32, frequencies: &[f32; MAX_CROSSOVERS]) {
+        let q = std::f32::consts::FRAC_1_SQRT_2;
+        for (crossover, frequency) in frequencies.iter().enumerate() {
+            let frequency = frequency.min(sample_rate * 0.45);
+            for stage in 0..2 {
+                self.lowpass[crossover][stage].set_lowpass(sample_rate, frequency, q);
+                self.highpass[crossover][stage].set_highpass(sample_rate, frequency, q);
+            }
+            for band in self.allpass.iter_mut() {
+                band[crossover].set_allpass(sample_rate, frequency, q);
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for biquad in self
+            .lowpass
+            .iter_mut()
+            .chain(self.highpass.iter_mut())
+            .flatten()
+            .chain(self.allpass.iter_mut().flatten())
+        {
+            biquad.reset();
+        }
+    }
+
+    /// Splits `sample` into `band_count` bands, from low to high. The unused bands are silent.
+    pub fn split(&mut self, sample: f32, band_count: usize) -> [f32; MAX_DISTORTION_BANDS] {
+        let mut bands = [0.0; MAX_DISTORTION_BANDS];
+        let mut rest = sample;
+        for ((band, [lowpass_1, lowpass_2]), [highpass_1, highpass_2]) in bands
+            .iter_mut()
+            .zip(self.lowpass.iter_mut())
+            .zip(self.highpass.iter_mut())
+            .take(band_count - 1)
+        {
+            *band = lowpass_2.process(lowpass_1.process(rest));
+            rest = highpass_2.process(highpass_1.process(rest));
+        }
+        bands[band_count - 1] = rest;
+
+        for (band, sample) in bands.iter_mut().enumerate().take(band_count) {
+            for crossover in band + 1..band_count - 1 {
+                *sample = self.allpass[band][crossover].process(*sample);
+            }
+        }
+
+        bands
+    }
+}
+
+// The imager splits with the same crossovers as the multiband distortion
+const MAX_IMAGER_BANDS: usize = MAX_DISTORTION_BANDS;
+
+/// Everything the [`Imager`] needs for a block, resolved once from the parameters.
+pub struct ImagerSettings {
+    pub band_count: usize,
+    /// Side level of every band, 0 is mono, 1 leaves the band as is and 2 doubles the side.
+    pub widths: [f32; MAX_IMAGER_BANDS],
+    pub bass_mono: bool,
+}
+
+/// Multiband stereo imager. Left and right go through identical crossovers, so every band keeps
+/// its stereo image intact until its side level gets changed.
+#[derive(Default)]
+pub struct Imager {
+    crossovers: [Crossover; 2],
+    bass_mono: [Crossover; 2],
+}
+
+impl Imager {
+    /// `crossover_frequencies` must be sorted.
+    pub fn set_frequencies(
+        &mut self,
+        sample_rate: f32,
+        crossover_frequencies: &[f32; MAX_CROSSOVERS],
+        bass_mono_frequency: f32,
+    ) {
+        for (crossover, bass_mono) in self.crossovers.iter_mut().zip(self.bass_mono.iter_mut()) {
+            crossover.set_frequencies(sample_rate, crossover_frequencies);
+            bass_mono.set_frequencies(sample_rate, &[bass_mono_frequency; MAX_CROSSOVERS]);
+        }
+    }
+
+    pub fn process(&mut self, left: f32, right: f32, settings: &ImagerSettings) -> (f32, f32) {
+        let (left, right) = if settings.bass_mono {
+            let [left_low, left_high, ..] = self.bass_mono[0].split(left, 2);
+            let [right_low, right_high, ..] = self.bass_mono[1].split(right, 2);
+            let low = (left_low + right_low) * 0.5;
+
+            (low + left_high, low + right_high)
+        } else {
+            (left, right)
+        };
+
+        if settings.band_count == 1 {
+            return imager_width(left, right, settings.widths[0]);
+        }
+
+        let left_bands = self.crossovers[0].split(left, settings.band_count);
+        let right_bands = self.crossovers[1].split(right, settings.band_count);
+        left_bands
+            .into_iter()
+            .zip(right_bands)
+            .zip(settings.widths)
+            .take(settings.band_count)
+            .map(|((left, right), width)| imager_width(left, right, width))
+            .fold((0.0, 0.0), |(left_sum, right_sum), (left, right)| {
+                (left_sum + left, right_sum + right)
+            })
+    }
+}
+
+/// Scales the side while leaving the mid alone, so narrowing never changes the level of the
+/// center.
+fn imager_width(left: f32, right: f32, width: f32) -> (f32, f32) {
+    let (mid, side) = mid_side_encode(left, right, 1.0);
+    mid_side_decode(mid, side * width)
+}
+
+/// Everything a [`DistortionBand`] needs for a block, resolved once from the parameters.
+pub struct BandSettings<'a> {
+    pub shaper: ShaperSettings<'a>,
+    pub antialiasing: Antialiasing,
+    pub drive: f32,
+    pub makeup_gain: f32,
+    pub bias: f32,
+    pub bias_offset: f32,
+    pub mix: f32,
+    pub oversampling_stages: usize,
+    /// Latency of the whole effect, the band's dry signal is delayed by this much.
+    pub latency: usize,
+    /// Extra delay for bands that are faster than the slowest band, so they all line up.
+    pub alignment: usize,
+    pub sample_rate: f32,
+    pub bit_crush_rate: f32,
+    pub bit_crush_jitter: f32,
+    pub bit_crush_prefilter: bool,
+    pub tape_hysteresis: f32,
+}
+
+/// The shaping part of the distortion for one band of one channel. Without multiband processing
+/// only the first band is used.
+pub struct DistortionBand {
+    pub oversampler: Oversampler,
+    pub adaa: Adaa,
+    pub decimator: Decimator,
+    pub tape: Tape,
+    pub dry_delay: SampleDelay,
+    pub alignment: SampleDelay,
+}
+
+impl Default for DistortionBand {
+    fn default() -> Self {
+        Self {
+            oversampler: Oversampler::default(),
+            adaa: Adaa::default(),
+            decimator: Decimator::default(),
+            tape: Tape::default(),
+            dry_delay: SampleDelay::new(128),
+            alignment: SampleDelay::new(2),
+        }
+    }
+}
+
+impl DistortionBand {
+    pub fn process(&mut self, sample: f32, settings: &BandSettings) -> f32 {
+        let dry = self.dry_delay.process(sample, settings.latency);
+        let mut driven = sample * settings.drive;
+
+        // Rate reduction is meant to alias, so it happens at the host rate before oversampling
+        if *settings.shaper.mode == DistortionMode::BitCrush {
+            driven = self.decimator.process(
+                driven,
+                settings.sample_rate,
+                settings.bit_crush_rate,
+                settings.bit_crush_jitter,
+                settings.bit_crush_prefilter,
+            );
+        }
+
+        let is_tape = *settings.shaper.mode == DistortionMode::Tape;
+        if is_tape {
+            driven = self.tape.record(driven);
+        }
+
+        let adaa = &mut self.adaa;
+        let tape = &mut self.tape;
+        let mut shaped = self
+            .oversampler
+            .process(driven, settings.oversampling_stages, |sample| {
+                let sample = sample + settings.bias;
+                let shaped = if is_tape {
+                    tape.magnetize(sample, settings.tape_hysteresis, &settings.shaper)
+                } else {
+                    adaa.process(sample, settings.antialiasing, &settings.shaper)
+                };
+
+                shaped - settings.bias_offset
+            });
+
+        if is_tape {
+            shaped = self.tape.playback(shaped);
+        }
+
+        let wet = self.alignment.process(shaped, settings.alignment) * settings.makeup_gain;
+
+        lerp(dry, wet, settings.mix)
+    }
+}
+
+/// State for one channel of the distortion effect.
+pub struct DistortionChannel {
+    pub dry_delay: SampleDelay,
+    pub pre_emphasis: TiltFilter,
+    pub de_emphasis: TiltFilter,
+    pub tone: TiltFilter,
+    pub dc_blocker: DcBlocker,
+    pub crossover: Crossover,
+    pub bands: [DistortionBand; MAX_DISTORTION_BANDS],
+}
+
+impl Default for DistortionChannel {
+    fn default() -> Self {
+        Self {
+            dry_delay: SampleDelay::new(128),
+            pre_emphasis: TiltFilter::default(),
+            de_emphasis: TiltFilter::default(),
+            tone: TiltFilter::default(),
+            dc_blocker: DcBlocker::default(),
+            crossover: Crossover::default(),
+            bands: Default::default(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Distortion {
+    pub channels: [DistortionChannel; 2],
+    pub oversampling_stages: usize,
+    pub band_count: usize,
+    pub custom_curve: TransferCurve,
+}
+
+impl Distortion {
+    /// Latency in samples at the host rate. Second order ADAA adds a full sample on top of the
+    /// oversampling filters, but only at 1x since it's a fraction of a host sample otherwise.
+    pub fn latency(oversampling_stages: usize, antialiasing: Antialiasing) -> u32 {
+        let adaa_latency = match (oversampling_stages, antialiasing) {
+            (0, Antialiasing::SecondOrder) => 1,
+            _ => 0,
+        };
+
+        Oversampler::latency(oversampling_stages) + adaa_latency
+    }
+}
+
+/// This is mostly identical to the gain example, minus some fluff, and with a GUI.
+pub struct HackAudio {
+    params: Arc<PluginParams>,
+    ui_state: UiState,
+    /// The audio thread's copy of [`PluginParams::view_settings`], so it never waits on the
+    /// editor.
+    view_settings: ViewSettings,
+    host_sample_rate: f32,
+    goniometer: Arc<SampleRing>,
+    stereo_meter: StereoMeter,
+    stereo_meter_input: triple_buffer::Input<StereoMeterReadout>,
+    stereo_meter_output: Arc<Mutex<triple_buffer::Output<StereoMeterReadout>>>,
+    spectrum_analyzer: SpectrumAnalyzer,
+    spectrum_input: triple_buffer::Input<SpectrumFrame>,
+    spectrum_output: Arc<Mutex<triple_buffer::Output<SpectrumFrame>>>,
+    spectrogram_input: triple_buffer::Input<SpectrumFrame>,
+    spectrogram_output: Arc<Mutex<triple_buffer::Output<SpectrumFrame>>>,
+    loudness_meter: LoudnessMeter,
+    loudness_input: triple_buffer::Input<LoudnessReadout>,
+    loudness_output: Arc<Mutex<triple_buffer::Output<LoudnessReadout>>>,
+    input_detector: LevelDetector,
+    input_meter: Arc<LevelMeter>,
+    output_detector: LevelDetector,
+    output_meter: Arc<LevelMeter>,
+    oscilloscope: Arc<Oscilloscope>,
+    // The block's input, kept until the effect is done so it can be paired with the output
+    oscilloscope_input: Vec<f32>,
+    mid_side_wrapper: MidSideWrapper,
+    imager: Imager,
+    haas: Haas,
+    binaural: Binaural,
+    haas_meter_input: triple_buffer::Input<StereoMeterReadout>,
+    haas_meter_output: Arc<Mutex<triple_buffer::Output<StereoMeterReadout>>>,
+    delay_buffer: DelayBuffer,
+    chorus: [Chorus; 2],
+    distortion: Distortion,
+    latency_samples: u32,
+    // The effect that ran last block, so an effect's state can be cleared when it's switched to
+    previous_fx: Fx,
+}
+
+#[derive(Params)]
+pub struct PluginParams {
+    /// The editor state, saved together with the parameter state so the custom scaling can be
+    /// restored.
+    #[persist = "editor-state"]
+    editor_state: Arc<EguiState>,
+
+    /// Points of the user drawn curve for [`DistortionMode::Custom`], sorted by x with the first
+    /// and last points pinned to x = -1 and x = 1.
+    #[persist = "distortion-custom-curve"]
+    pub custom_curve: Arc<RwLock<Vec<(f32, f32)>>>,
+
+    /// Set by the editor whenever `custom_curve` changes so the audio thread knows to rebake its
+    /// lookup table.
+    pub custom_curve_changed: AtomicBool,
+
+    /// Analyzer and meter display options, see [`ViewSettings`].
+    #[persist = "view-settings"]
+    pub view_settings: Arc<RwLock<ViewSettings>>,
+
+    #[id = "selected_fx"]
+    pub selected_fx: EnumParam<Fx>,
+
+    #[id = "pan"]
+    pub pan: FloatParam,
+
+    #[id = "panning_mode"]
+    pub panning_mode: EnumParam<PanningMode>,
+
+    #[id = "panning-source"]
+    pub panning_source: EnumParam<PanningSource>,
+
+    #[id = "stereo-pan-width"]
+    pub stereo_pan_width: FloatParam,
+
+    #[id = "binaural-azimuth"]
+    pub binaural_azimuth: FloatParam,
+
+    #[id = "binaural-elevation"]
+    pub binaural_elevation: FloatParam,
+
+    #[id = "mid-side-encoding-stereo-width"]
+    pub mid_side_enc_stereo_width: FloatParam,
+
+    #[id = "monitor-mode"]
+    pub monitor_mode: EnumParam<MonitorMode>,
+
+    #[id = "monitor-invert-left"]
+    pub monitor_invert_left: BoolParam,
+
+    #[id = "monitor-invert-right"]
+    pub monitor_invert_right: BoolParam,
+
+    #[id = "imager-band-count"]
+    pub imager_band_count: IntParam,
+
+    #[id = "imager-crossover-low"]
+    pub imager_crossover_low: FloatParam,
+
+    #[id = "imager-crossover-mid"]
+    pub imager_crossover_mid: FloatParam,
+
+    #[id = "imager-crossover-high"]
+    pub imager_crossover_high: FloatParam,
+
+    #[nested(array, group = "Imager Bands")]
+    pub imager_bands: [ImagerBandParams; MAX_IMAGER_BANDS],
+
+    #[id = "imager-bass-mono"]
+    pub imager_bass_mono: BoolParam,
+
+    #[id = "imager-bass-mono-frequency"]
+    pub imager_bass_mono_frequency: FloatParam,
+
+    #[id = "haas-delay"]
+    pub haas_delay: FloatParam,
+
+    #[id = "haas-channel"]
+    pub haas_channel: EnumParam<HaasChannel>,
+
+    /// Level of the delayed channel. The precedence effect pulls the image towards the channel
+    /// that arrives first, raising the delayed channel is how that's compensated for.
+    #[id = "haas-level"]
+    pub haas_level: FloatParam,
+
+    #[id = "haas-filter"]
+    pub haas_filter: BoolParam,
+
+    #[id = "haas-highpass"]
+    pub haas_highpass: FloatParam,
+
+    #[id = "haas-lowpass"]
+    pub haas_lowpass: FloatParam,
+
+    #[id = "mid-side-processing"]
+    pub mid_side_processing: BoolParam,
+
+    #[id = "mid-side-mid-mix"]
+    pub mid_side_mid_mix: FloatParam,
+
+    #[id = "mid-side-side-mix"]
+    pub mid_side_side_mix: FloatParam,
+
+    #[id = "mid-side-mid-gain"]
+    pub mid_side_mid_gain: FloatParam,
+
+    #[id = "mid-side-side-gain"]
+    pub mid_side_side_gain: FloatParam,
+
+    #[nested(array, group = "Mid-Side Paths")]
+    pub mid_side_paths: [MidSidePathParams; 2],
+
+    /// Set by the editor's reset button, the audio thread then starts a fresh loudness
+    /// measurement.
+    pub loudness_reset: AtomicBool,
+
+    #[id = "distortion_mode"]
+    pub distortion_mode: EnumParam<DistortionMode>,
+
+    #[id = "distortion_amount"]
+    pub distortion_amount: FloatParam,
+
+    // Used to be a whole number of bits under "bit-crush-bits", see `HackAudio::filter_state()`
+    #[id = "bit-crush-depth"]
+    pub bit_crush_bits: FloatParam,
+
+    #[id = "bit-crush-rate"]
+    pub bit_crush_rate: FloatParam,
+
+    #[id = "bit-crush-jitter"]
+    pub bit_crush_jitter: FloatParam,
+
+    #[id = "bit-crush-prefilter"]
+    pub bit_crush_prefilter: BoolParam,
+
+    #[id = "distortion-mix"]
+    pub distortion_mix: FloatParam,
+
+    #[id = "distortion-drive"]
+    pub distortion_drive: FloatParam,
+
+    #[id = "distortion-auto-gain"]
+    pub distortion_auto_gain: BoolParam,
+
+    #[id = "distortion-output"]
+    pub distortion_output: FloatParam,
+
+    #[id = "distortion-bias"]
+    pub distortion_bias: FloatParam,
+
+    #[id = "distortion-emphasis"]
+    pub distortion_emphasis: FloatParam,
+
+    #[id = "distortion-tone"]
+    pub distortion_tone: FloatParam,
+
+    #[id = "distortion-oversampling"]
+    pub distortion_oversampling: EnumParam<Oversampling>,
+
+    #[id = "cubic-adaa"]
+    pub cubic_adaa: EnumParam<Antialiasing>,
+
+    #[id = "arctangent-adaa"]
+    pub arctangent_adaa: EnumParam<Antialiasing>,
+
+    #[id = "inf-clip-adaa"]
+    pub inf_clip_adaa: EnumParam<Antialiasing>,
+
+    #[id = "exp-soft-clip-adaa"]
+    pub exp_soft_clip_adaa: EnumParam<Antialiasing>,
+
+    #[id = "triode-bias"]
+    pub triode_bias: FloatParam,
+
+    #[id = "tape-hysteresis"]
+    pub tape_hysteresis: FloatParam,
+
+    #[id = "tape-speed"]
+    pub tape_speed: EnumParam<TapeSpeed>,
+
+    #[id = "fold-count"]
+    pub fold_count: FloatParam,
+
+    #[id = "sine-fold-adaa"]
+    pub sine_fold_adaa: EnumParam<Antialiasing>,
+
+    #[id = "half-wave-rect-adaa"]
+    pub half_wave_rect_adaa: EnumParam<Antialiasing>,
+
+    #[id = "full-wave-rect-adaa"]
+    pub full_wave_rect_adaa: EnumParam<Antialiasing>,
+
+    #[id = "distortion-band-count"]
+    pub distortion_band_count: IntParam,
+
+    #[id = "distortion-crossover-low"]
+    pub distortion_crossover_low: FloatParam,
+
+    #[id = "distortion-crossover-mid"]
+    pub distortion_crossover_mid: FloatParam,
+
+    #[id = "distortion-crossover-high"]
+    pub distortion_crossover_high: FloatParam,
+
+    #[nested(array, group = "Distortion Bands")]
+    pub distortion_bands: [DistortionBandParams; MAX_DISTORTION_BANDS],
+
+    // TODO: Make all of the delay params FloatParam
+    // Then skew the Range so the top half has more play
+    // Also need to step by 1.0
+    #[id = "delay-feedback"]
+    pub delay_feedback: IntParam,
+
+    #[id = "delay-time"]
+    pub delay_time: IntParam,
+
+    #[id = "delay-mix-in"]
+    pub delay_mix_in: IntParam,
+
+    #[id = "delay-wet-out"]
+    pub delay_wet_out: IntParam,
+
+    #[id = "delay-dry-out"]
+    pub delay_dry_out: IntParam,
+
+    #[id = "chorus-time"]
+    pub chorus_time: FloatParam,
+
+    #[id = "chorus-voice-count"]
+    pub chorus_voice_count: IntParam,
+
+    #[id = "chorus-rate"]
+    pub chorus_rate: FloatParam,
+
+    #[id = "chorus-depth"]
+    pub chorus_depth: FloatParam,
+
+    #[id = "chorus-out-mix-wet"]
+    pub chorus_out_mix_wet: FloatParam,
+
+    #[id = "chorus-out-mix-dry"]
+    pub chorus_out_mix_dry: FloatParam,
+}
+
+
+impl Default for HackAudio {
+    fn default() -> Self {
+        let (stereo_meter_input, stereo_meter_output) = TripleBuffer::default().split();
+        let (spectrum_input, spectrum_output) = TripleBuffer::default().split();
+        let (spectrogram_input, spectrogram_output) = TripleBuffer::default().split();
+        let (loudness_input, loudness_output) = TripleBuffer::default().split();
+        let (haas_meter_input, haas_meter_output) = TripleBuffer::default().split();
+        Self {
+            params: Arc::new(PluginParams::default()),
+            goniometer: Arc::new(SampleRing::new(GONIOMETER_RING_CAPACITY)),
+            stereo_meter: StereoMeter::default(),
+            stereo_meter_input,
+            stereo_meter_output: Arc::new(Mutex::new(stereo_meter_output)),
+            spectrum_analyzer: SpectrumAnalyzer::default(),
+            spectrum_input,
+            spectrum_output: Arc::new(Mutex::new(spectrum_output)),
+            spectrogram_input,
+            spectrogram_output: Arc::new(Mutex::new(spectrogram_output)),
+            loudness_meter: LoudnessMeter::default(),
+            loudness_input,
+            loudness_output: Arc::new(Mutex::new(loudness_output)),
+            input_detector: LevelDetector::default(),
+            input_meter: Arc::new(LevelMeter::default()),
+            output_detector: LevelDetector::default(),
+            output_meter: Arc::new(LevelMeter::default()),
+            oscilloscope: Arc::new(Oscilloscope::default()),
+            oscilloscope_input: Vec::new(),
+            mid_side_wrapper: MidSideWrapper::default(),
+            imager: Imager::default(),
+            haas: Haas::default(),
+            binaural: Binaural::default(),
+            haas_meter_input,
+            haas_meter_output: Arc::new(Mutex::new(haas_meter_output)),
+            delay_buffer: DelayBuffer::default(),
+            chorus: [Chorus::default(), Chorus::default()],
+            distortion: Distortion::default(),
+            latency_samples: 0,
+            previous_fx: Fx::Panning,
+            host_sample_rate: 44100.0,
+            ui_state: UiState::default(),
+            view_settings: ViewSettings::default(),
+        }
+    }
+}
+
+impl Default for PluginParams {
+    fn default() -> Self {
+        Self {
+            editor_state: EguiState::from_size(600, 800),
+            custom_curve: Arc::new(RwLock::new(default_custom_curve())),
+            custom_curve_changed: AtomicBool::new(true),
+            view_settings: Arc::new(RwLock::new(ViewSettings::default())),
+
+            selected_fx: EnumParam::new("Selected Fx", Fx::Panning),
+            panning_mode: EnumParam::new("Panning Mode", PanningMode::Linear),
+            panning_source: EnumParam::new("Panning Source", PanningSource::Mono),
+            stereo_pan_width: FloatParam::new(
+                "Stereo Pan Width",
+                100.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 100.0,
+                },
+            )
+            .with_unit(" %")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            binaural_azimuth: FloatParam::new(
+                "Binaural Azimuth",
+                0.0,
+                FloatRange::Linear {
+                    min: -180.0,
+                    max: 180.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_unit("°")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            binaural_elevation: FloatParam::new(
+                "Binaural Elevation",
+                0.0,
+                FloatRange::Linear {
+                    min: -90.0,
+                    max: 90.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_unit("°")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
             distortion_mode: EnumParam::new("Distortion Mode", DistortionMode::Cubic),
             pan: FloatParam::new(
                 "Pan",
                 0.0,
                 FloatRange::Linear {
-                    min: -100.0,
-                    max: 100.0,
+                    min: -100.0,
+                    max: 100.0,
+                },
+            )
+            .with_unit(" %")
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            mid_side_enc_stereo_width: FloatParam::new(
+                "Stereo Width",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 2.0 },
+            )
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            monitor_mode: EnumParam::new("Monitor Mode", MonitorMode::Stereo),
+            monitor_invert_left: BoolParam::new("Monitor Invert Left", false),
+            monitor_invert_right: BoolParam::new("Monitor Invert Right", false),
+
+            imager_band_count: IntParam::new(
+                "Imager Bands",
+                3,
+                IntRange::Linear {
+                    min: 1,
+                    max: MAX_IMAGER_BANDS as i32,
+                },
+            ),
+            imager_crossover_low: crossover_param("Imager Crossover Low", 150.0),
+            imager_crossover_mid: crossover_param("Imager Crossover Mid", 2_000.0),
+            imager_crossover_high: crossover_param("Imager Crossover High", 8_000.0),
+            imager_bands: std::array::from_fn(ImagerBandParams::new),
+            imager_bass_mono: BoolParam::new("Imager Bass Mono", false),
+            imager_bass_mono_frequency: FloatParam::new(
+                "Imager Bass Mono Frequency",
+                120.0,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 500.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_unit(" Hz")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+
+            haas_delay: FloatParam::new(
+                "Haas Delay",
+                15.0,
+                FloatRange::Linear {
+                    min: 1.0,
+                    max: HAAS_MAX_DELAY_MS,
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            haas_channel: EnumParam::new("Haas Delayed Channel", HaasChannel::Right),
+            haas_level: FloatParam::new(
+                "Haas Delayed Level",
+                0.0,
+                FloatRange::Linear {
+                    min: -12.0,
+                    max: 6.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            haas_filter: BoolParam::new("Haas Filter", false),
+            haas_highpass: FloatParam::new(
+                "Haas Highpass",
+                200.0,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 2_000.0,
+                    factor: FloatRange::skew_factor(-1.5),
+                },
+            )
+            .with_unit(" Hz")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            haas_lowpass: FloatParam::new(
+                "Haas Lowpass",
+                8_000.0,
+                FloatRange::Skewed {
+                    min: 1_000.0,
+                    max: 20_000.0,
+                    factor: FloatRange::skew_factor(-1.5),
+                },
+            )
+            .with_unit(" Hz")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+
+            mid_side_processing: BoolParam::new("M/S Processing", false),
+            mid_side_mid_mix: mid_side_mix_param("M/S Mid Mix"),
+            mid_side_side_mix: mid_side_mix_param("M/S Side Mix"),
+            mid_side_mid_gain: mid_side_gain_param("M/S Mid Gain"),
+            mid_side_side_gain: mid_side_gain_param("M/S Side Gain"),
+            mid_side_paths: std::array::from_fn(MidSidePathParams::new),
+
+            loudness_reset: AtomicBool::new(false),
+
+            distortion_amount: FloatParam::new(
+                "Distortion Amount",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            distortion_mix: FloatParam::new(
+                "Distortion Mix",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 100.0,
+                },
+            )
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            bit_crush_bits: FloatParam::new(
+                "BitCrush Bits",
+                16.0,
+                FloatRange::Linear {
+                    min: 1.0,
+                    max: 16.0,
                 },
             )
-            .with_unit(" %")
             .with_value_to_string(formatters::v2s_f32_rounded(2)),
 
-            mid_side_enc_stereo_width: FloatParam::new(
-                "Stereo Width",
-                0.0,
-                FloatRange::Linear { min: 0.0, max: 2.0 },
+            bit_crush_rate: FloatParam::new(
+                "BitCrush Rate",
+                BIT_CRUSH_RATE_OFF,
+                FloatRange::Skewed {
+                    min: 100.0,
+                    max: BIT_CRUSH_RATE_OFF,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
             )
-            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+            .with_value_to_string(Arc::new(|value| {
+                if value >= BIT_CRUSH_RATE_OFF {
+                    String::from("Off")
+                } else {
+                    format!("{value:.0} Hz")
+                }
+            }))
+            .with_string_to_value(Arc::new(|string| {
+                let string = string.trim();
+                if string.eq_ignore_ascii_case("off") {
+                    Some(BIT_CRUSH_RATE_OFF)
+                } else {
+                    string.trim_end_matches("Hz").trim().parse().ok()
+                }
+            })),
 
-            distortion_amount: FloatParam::new(
-                "Distortion Amount",
+            bit_crush_jitter: FloatParam::new(
+                "BitCrush Jitter",
                 0.0,
                 FloatRange::Linear { min: 0.0, max: 1.0 },
             )
             .with_value_to_string(formatters::v2s_f32_rounded(2)),
 
-            distortion_mix: FloatParam::new(
-                "Distortion Mix",
+            bit_crush_prefilter: BoolParam::new("BitCrush Prefilter", true),
+
+            distortion_drive: FloatParam::new(
+                "Distortion Drive",
                 0.0,
                 FloatRange::Linear {
                     min: 0.0,
-                    max: 100.0,
+                    max: 36.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            distortion_auto_gain: BoolParam::new("Distortion Auto Gain", true),
+
+            distortion_output: FloatParam::new(
+                "Distortion Output",
+                0.0,
+                FloatRange::Linear {
+                    min: -24.0,
+                    max: 24.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            distortion_bias: FloatParam::new(
+                "Distortion Bias",
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
                 },
             )
             .with_value_to_string(formatters::v2s_f32_rounded(2)),
 
-            bit_crush_bits: IntParam::new(
-                "BitCrush Bits",
-                16,
-                IntRange::Linear { min: 1, max: 16 },
+            distortion_emphasis: FloatParam::new(
+                "Distortion Emphasis",
+                0.0,
+                FloatRange::Linear {
+                    min: -12.0,
+                    max: 12.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            distortion_tone: FloatParam::new(
+                "Distortion Tone",
+                0.0,
+                FloatRange::Linear {
+                    min: -12.0,
+                    max: 12.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            distortion_oversampling: EnumParam::new("Distortion Oversampling", Oversampling::X1),
+            cubic_adaa: EnumParam::new("Cubic Antialiasing", Antialiasing::Off),
+            arctangent_adaa: EnumParam::new("ArcTangent Antialiasing", Antialiasing::Off),
+            inf_clip_adaa: EnumParam::new("Infinite Clipping Antialiasing", Antialiasing::Off),
+            exp_soft_clip_adaa: EnumParam::new(
+                "Exponential Soft Clipping Antialiasing",
+                Antialiasing::Off,
+            ),
+
+            triode_bias: FloatParam::new(
+                "Triode Bias",
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            tape_hysteresis: FloatParam::new(
+                "Tape Hysteresis",
+                0.3,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            tape_speed: EnumParam::new("Tape Speed", TapeSpeed::Ips15),
+
+            fold_count: FloatParam::new(
+                "Wavefolder Folds",
+                2.0,
+                FloatRange::Linear { min: 1.0, max: 8.0 },
+            )
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+            sine_fold_adaa: EnumParam::new("Sine Fold Antialiasing", Antialiasing::Off),
+            half_wave_rect_adaa: EnumParam::new(
+                "Half Wave Rectifier Antialiasing",
+                Antialiasing::Off,
+            ),
+            full_wave_rect_adaa: EnumParam::new(
+                "Full Wave Rectifier Antialiasing",
+                Antialiasing::Off,
+            ),
+
+            distortion_band_count: IntParam::new(
+                "Distortion Bands",
+                1,
+                IntRange::Linear {
+                    min: 1,
+                    max: MAX_DISTORTION_BANDS as i32,
+                },
             ),
+            distortion_crossover_low: crossover_param("Distortion Crossover Low", 200.0),
+            distortion_crossover_mid: crossover_param("Distortion Crossover Mid", 1_000.0),
+            distortion_crossover_high: crossover_param("Distortion Crossover High", 5_000.0),
+            distortion_bands: std::array::from_fn(DistortionBandParams::new),
 
             delay_feedback: IntParam::new(
                 "Delay Feedback",
@@ -451,6 +3565,288 @@ impl Default for PluginParams {
     }
 }
 
+fn crossover_param(name: &str, default: f32) -> FloatParam {
+    FloatParam::new(
+        name,
+        default,
+        FloatRange::Skewed {
+            min: 20.0,
+            max: 20_000.0,
+            factor: FloatRange::skew_factor(-2.0),
+        },
+    )
+    .with_unit(" Hz")
+    .with_value_to_string(formatters::v2s_f32_rounded(0))
+}
+
+fn mid_side_mix_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        100.0,
+        FloatRange::Linear {
+            min: 0.0,
+            max: 100.0,
+        },
+    )
+    .with_unit(" %")
+    .with_value_to_string(formatters::v2s_f32_rounded(0))
+}
+
+fn mid_side_gain_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        0.0,
+        FloatRange::Linear {
+            min: -24.0,
+            max: 24.0,
+        },
+    )
+    .with_unit(" dB")
+    .with_value_to_string(formatters::v2s_f32_rounded(1))
+}
+
+/// Settings for one band of the multiband distortion.
+#[derive(Params)]
+pub struct DistortionBandParams {
+    #[id = "mode"]
+    pub mode: EnumParam<DistortionMode>,
+
+    #[id = "drive"]
+    pub drive: FloatParam,
+
+    #[id = "mix"]
+    pub mix: FloatParam,
+}
+
+impl DistortionBandParams {
+    pub fn new(index: usize) -> Self {
+        Self {
+            mode: EnumParam::new(format!("Band {} Mode", index + 1), DistortionMode::Cubic),
+            drive: FloatParam::new(
+                format!("Band {} Drive", index + 1),
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 36.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            mix: FloatParam::new(
+                format!("Band {} Mix", index + 1),
+                100.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 100.0,
+                },
+            )
+            .with_unit(" %")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+        }
+    }
+}
+
+/// Settings for one band of the stereo imager.
+#[derive(Params)]
+pub struct ImagerBandParams {
+    #[id = "width"]
+    pub width: FloatParam,
+}
+
+impl ImagerBandParams {
+    pub fn new(index: usize) -> Self {
+        Self {
+            width: FloatParam::new(
+                format!("Imager Band {} Width", index + 1),
+                100.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 200.0,
+                },
+            )
+            .with_unit(" %")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+        }
+    }
+}
+
+/// Effect settings the mid and side paths each get to themselves in mid/side processing, in
+/// place of the effect's own. Everything else is shared between the paths.
+#[derive(Params)]
+pub struct MidSidePathParams {
+    #[id = "mid-side-distortion-amount"]
+    pub distortion_amount: FloatParam,
+
+    #[id = "mid-side-distortion-drive"]
+    pub distortion_drive: FloatParam,
+
+    #[id = "mid-side-distortion-mix"]
+    pub distortion_mix: FloatParam,
+
+    #[id = "mid-side-delay-time"]
+    pub delay_time: IntParam,
+
+    #[id = "mid-side-delay-feedback"]
+    pub delay_feedback: IntParam,
+
+    #[id = "mid-side-delay-wet-out"]
+    pub delay_wet_out: IntParam,
+
+    #[id = "mid-side-chorus-time"]
+    pub chorus_time: FloatParam,
+
+    #[id = "mid-side-chorus-depth"]
+    pub chorus_depth: FloatParam,
+
+    #[id = "mid-side-chorus-out-mix-wet"]
+    pub chorus_out_mix_wet: FloatParam,
+}
+
+impl MidSidePathParams {
+    /// Index 0 is the mid path and index 1 the side path.
+    pub fn new(index: usize) -> Self {
+        let path = if index == 0 { "Mid" } else { "Side" };
+
+        Self {
+            distortion_amount: FloatParam::new(
+                format!("M/S {path} Distortion Amount"),
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+            distortion_drive: FloatParam::new(
+                format!("M/S {path} Distortion Drive"),
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 36.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            distortion_mix: FloatParam::new(
+                format!("M/S {path} Distortion Mix"),
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 100.0,
+                },
+            )
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+            delay_time: IntParam::new(
+                format!("M/S {path} Delay Time"),
+                300,
+                IntRange::Linear { min: 50, max: 1000 },
+            )
+            .with_unit(" ms"),
+            delay_feedback: IntParam::new(
+                format!("M/S {path} Delay Feedback"),
+                -5,
+                IntRange::Linear { min: -120, max: 6 },
+            )
+            .with_unit(" dB"),
+            delay_wet_out: IntParam::new(
+                format!("M/S {path} Delay Wet Out"),
+                -6,
+                IntRange::Linear { min: -120, max: 6 },
+            )
+            .with_unit(" dB"),
+            chorus_time: FloatParam::new(
+                format!("M/S {path} Chorus Time"),
+                15.0,
+                FloatRange::Linear {
+                    min: 1.0,
+                    max: 250.0,
+                },
+            )
+            .with_unit(" ms"),
+            chorus_depth: FloatParam::new(
+                format!("M/S {path} Chorus Depth"),
+                0.7,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            chorus_out_mix_wet: FloatParam::new(
+                format!("M/S {path} Chorus Out Mix Wet"),
+                -6.0,
+                FloatRange::Linear {
+                    min: -100.0,
+                    max: 12.0,
+                },
+            )
+            .with_unit(" dB"),
+        }
+    }
+}
+
+impl PluginParams {
+    /// Number of bands the distortion splits into, 1 means multiband processing is off.
+    pub fn distortion_band_count(&self) -> usize {
+        self.distortion_band_count.value() as usize
+    }
+
+    /// Multiband processing gives every band its own mode, otherwise the main mode is used.
+    pub fn distortion_band_mode(&self, band: usize) -> DistortionMode {
+        if self.distortion_band_count() > 1 {
+            self.distortion_bands[band].mode.value()
+        } else {
+            self.distortion_mode.value()
+        }
+    }
+
+    /// Amount and drive in dB the distortion runs with. In mid/side processing `path` picks the
+    /// mid or the side path's own settings.
+    pub fn distortion_amount_drive(&self, path: Option<usize>) -> (f32, f32) {
+        match path {
+            Some(path) => {
+                let path = &self.mid_side_paths[path];
+                (
+                    path.distortion_amount.value(),
+                    path.distortion_drive.value(),
+                )
+            }
+            None => (
+                self.distortion_amount.value(),
+                self.distortion_drive.value(),
+            ),
+        }
+    }
+
+    /// Latency of the slowest active band, the other bands are delayed to match it.
+    pub fn distortion_latency(&self) -> u32 {
+        let oversampling_stages = self.distortion_oversampling.value().stages();
+        (0..self.distortion_band_count())
+            .map(|band| {
+                let distortion_mode = self.distortion_band_mode(band);
+                Distortion::latency(oversampling_stages, self.antialiasing(&distortion_mode))
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Each shaper with closed form antiderivatives gets its own antialiasing setting.
+    pub fn antialiasing_param(
+        &self,
+        distortion_mode: &DistortionMode,
+    ) -> Option<&EnumParam<Antialiasing>> {
+        match distortion_mode {
+            DistortionMode::Cubic => Some(&self.cubic_adaa),
+            DistortionMode::ArcTangent => Some(&self.arctangent_adaa),
+            DistortionMode::InfiniteClipping => Some(&self.inf_clip_adaa),
+            DistortionMode::ExponentialSoftClipping => Some(&self.exp_soft_clip_adaa),
+            DistortionMode::SineFold => Some(&self.sine_fold_adaa),
+            DistortionMode::HalfWaveRectifier => Some(&self.half_wave_rect_adaa),
+            DistortionMode::FullWaveRectifier => Some(&self.full_wave_rect_adaa),
+            _ => None,
+        }
+    }
+
+    pub fn antialiasing(&self, distortion_mode: &DistortionMode) -> Antialiasing {
+        self.antialiasing_param(distortion_mode)
+            .map(|param| param.value())
+            .unwrap_or(Antialiasing::Off)
+    }
+}
+
 impl Plugin for HackAudio {
     const NAME: &'static str = "Hack Audio FX";
     const VENDOR: &'static str = "notryanb plugins";
@@ -481,20 +3877,41 @@ impl Plugin for HackAudio {
         self.params.clone()
     }
 
+    fn filter_state(state: &mut PluginState) {
+        // The bit depth was an integer parameter before fractional depths, nih-plug won't restore
+        // that into the float parameter that replaced it
+        if let Some(ParamValue::I32(bits)) = state.params.remove("bit-crush-bits") {
+            state
+                .params
+                .entry(String::from("bit-crush-depth"))
+                .or_insert(ParamValue::F32(bits as f32));
+        }
+    }
+
     fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
         let params = self.params.clone();
         let egui_state = params.editor_state.clone();
-        let goniometer = self.goniometer_output.clone();
+        let goniometer = self.goniometer.clone();
+        let stereo_meter = self.stereo_meter_output.clone();
+        let spectrum = self.spectrum_output.clone();
+        let spectrogram = self.spectrogram_output.clone();
+        let loudness = self.loudness_output.clone();
+        let input_meter = self.input_meter.clone();
+        let output_meter = self.output_meter.clone();
+        let oscilloscope = self.oscilloscope.clone();
+        let haas_meter = self.haas_meter_output.clone();
         create_egui_editor(
             self.params.editor_state.clone(),
             self.ui_state.clone(),
             |_, _| {},
-            move |egui_ctx, setter, _state| {
+            move |egui_ctx, setter, state| {
                 ResizableWindow::new("resizable-window")
                     .min_size(Vec2::new(400.0, 400.0))
                     .show(egui_ctx, egui_state.as_ref(), |_ui| {
                         let selected_fx = &params.selected_fx.value();
                         let panning_mode = &params.panning_mode.value();
+                        let mut view_settings = *params.view_settings.read();
+                        let previous_view_settings = view_settings;
                         let distortion_mode = &params.distortion_mode.value();
 
                         egui::TopBottomPanel::top("menu").show(egui_ctx, |ui| {
@@ -536,61 +3953,215 @@ impl Plugin for HackAudio {
                                 }
                                 if ui
                                     .add(egui::widgets::SelectableLabel::new(
-                                        *selected_fx == Fx::Goniometer,
-                                        "Stereo Visualizer",
+                                        *selected_fx == Fx::Goniometer,
+                                        "Stereo Visualizer",
+                                    ))
+                                    .clicked()
+                                {
+                                    setter.begin_set_parameter(&params.selected_fx);
+                                    setter.set_parameter(&params.selected_fx, Fx::Goniometer);
+                                    setter.end_set_parameter(&params.selected_fx);
+                                }
+
+                                if ui
+                                    .add(egui::widgets::SelectableLabel::new(
+                                        *selected_fx == Fx::Distortion,
+                                        "Distortion",
+                                    ))
+                                    .clicked()
+                                {
+                                    setter.begin_set_parameter(&params.selected_fx);
+                                    setter.set_parameter(&params.selected_fx, Fx::Distortion);
+                                    setter.end_set_parameter(&params.selected_fx);
+                                }
+
+                                if ui
+                                    .add(egui::widgets::SelectableLabel::new(
+                                        *selected_fx == Fx::Delay,
+                                        "Delay",
+                                    ))
+                                    .clicked()
+                                {
+                                    setter.begin_set_parameter(&params.selected_fx);
+                                    setter.set_parameter(&params.selected_fx, Fx::Delay);
+                                    setter.end_set_parameter(&params.selected_fx);
+                                }
+
+                                if ui
+                                    .add(egui::widgets::SelectableLabel::new(
+                                        *selected_fx == Fx::Chorus,
+                                        "Chorus",
+                                    ))
+                                    .clicked()
+                                {
+                                    setter.begin_set_parameter(&params.selected_fx);
+                                    setter.set_parameter(&params.selected_fx, Fx::Chorus);
+                                    setter.end_set_parameter(&params.selected_fx);
+                                }
+
+                                if ui
+                                    .add(egui::widgets::SelectableLabel::new(
+                                        *selected_fx == Fx::Analyzer,
+                                        "Analyzer",
                                     ))
                                     .clicked()
                                 {
                                     setter.begin_set_parameter(&params.selected_fx);
-                                    setter.set_parameter(&params.selected_fx, Fx::Goniometer);
+                                    setter.set_parameter(&params.selected_fx, Fx::Analyzer);
                                     setter.end_set_parameter(&params.selected_fx);
                                 }
 
                                 if ui
                                     .add(egui::widgets::SelectableLabel::new(
-                                        *selected_fx == Fx::Distortion,
-                                        "Distortion",
+                                        *selected_fx == Fx::Monitor,
+                                        "Monitor",
                                     ))
                                     .clicked()
                                 {
                                     setter.begin_set_parameter(&params.selected_fx);
-                                    setter.set_parameter(&params.selected_fx, Fx::Distortion);
+                                    setter.set_parameter(&params.selected_fx, Fx::Monitor);
                                     setter.end_set_parameter(&params.selected_fx);
                                 }
 
                                 if ui
                                     .add(egui::widgets::SelectableLabel::new(
-                                        *selected_fx == Fx::Delay,
-                                        "Delay",
+                                        *selected_fx == Fx::Imager,
+                                        "Imager",
                                     ))
                                     .clicked()
                                 {
                                     setter.begin_set_parameter(&params.selected_fx);
-                                    setter.set_parameter(&params.selected_fx, Fx::Delay);
+                                    setter.set_parameter(&params.selected_fx, Fx::Imager);
                                     setter.end_set_parameter(&params.selected_fx);
                                 }
 
                                 if ui
                                     .add(egui::widgets::SelectableLabel::new(
-                                        *selected_fx == Fx::Chorus,
-                                        "Chorus",
+                                        *selected_fx == Fx::Haas,
+                                        "Haas",
                                     ))
                                     .clicked()
                                 {
                                     setter.begin_set_parameter(&params.selected_fx);
-                                    setter.set_parameter(&params.selected_fx, Fx::Chorus);
+                                    setter.set_parameter(&params.selected_fx, Fx::Haas);
                                     setter.end_set_parameter(&params.selected_fx);
                                 }
+
+                                ui.separator();
+
+                                ui.toggle_value(
+                                    &mut view_settings.goniometer_overlay,
+                                    "Goniometer Overlay",
+                                );
+
+                                if view_settings.goniometer_overlay {
+                                    ui.selectable_value(
+                                        &mut view_settings.goniometer_tap,
+                                        AnalyzerTap::Pre,
+                                        "Pre",
+                                    );
+                                    ui.selectable_value(
+                                        &mut view_settings.goniometer_tap,
+                                        AnalyzerTap::Post,
+                                        "Post",
+                                    );
+                                }
+
+                                ui.separator();
+
+                                ui.toggle_value(&mut view_settings.loudness_meter, "Loudness");
+                                ui.toggle_value(&mut view_settings.spectrogram, "Spectrogram");
+                                ui.toggle_value(&mut view_settings.oscilloscope, "Oscilloscope");
+                            });
+
+                            ui.horizontal(|ui| {
+                                level_meter(ui, "In", &input_meter, &mut state.input_level);
+                                ui.separator();
+                                level_meter(ui, "Out", &output_meter, &mut state.output_level);
                             });
                         });
 
+                        if selected_fx.supports_mid_side() {
+                            egui::TopBottomPanel::top("mid-side").show(egui_ctx, |ui| {
+                                mid_side_wrapper_controls(ui, &params, setter);
+                            });
+                        }
+
+                        if view_settings.loudness_meter {
+                            egui::TopBottomPanel::bottom("loudness-meter").show(egui_ctx, |ui| {
+                                loudness_view(ui, &params, &loudness);
+                            });
+                        }
+
+                        if view_settings.oscilloscope {
+                            egui::TopBottomPanel::bottom("oscilloscope")
+                                .resizable(true)
+                                .default_height(200.0)
+                                .show(egui_ctx, |ui| {
+                                    oscilloscope_view(
+                                        ui,
+                                        &mut view_settings,
+                                        &oscilloscope,
+                                        &mut state.oscilloscope,
+                                    );
+                                });
+                        }
+
+                        if view_settings.spectrogram {
+                            egui::TopBottomPanel::bottom("spectrogram")
+                                .resizable(true)
+                                .default_height(200.0)
+                                .show(egui_ctx, |ui| {
+                                    spectrogram_view(
+                                        ui,
+                                        &mut view_settings,
+                                        &spectrogram,
+                                        &mut state.spectrogram,
+                                    );
+                                });
+                        }
+
+                        if view_settings.goniometer_overlay && *selected_fx != Fx::Goniometer {
+                            egui::SidePanel::right("goniometer-overlay").show(egui_ctx, |ui| {
+                                goniometer_view(
+                                    ui,
+                                    &mut view_settings,
+                                    &goniometer,
+                                    &stereo_meter,
+                                    &mut state.goniometer,
+                                );
+                            });
+                        }
+
                         egui::CentralPanel::default().show(egui_ctx, |ui| match selected_fx {
                             Fx::Panning => {
+                                ui.horizontal(|ui| {
+                                    let panning_source = params.panning_source.value();
+                                    for (source, label) in [
+                                        (PanningSource::Mono, "Mono Source"),
+                                        (PanningSource::Balance, "Balance"),
+                                        (PanningSource::Stereo, "Stereo Panner"),
+                                        (PanningSource::Binaural, "Binaural"),
+                                    ] {
+                                        if ui
+                                            .add(egui::widgets::SelectableLabel::new(
+                                                panning_source == source,
+                                                label,
+                                            ))
+                                            .clicked()
+                                        {
+                                            setter.begin_set_parameter(&params.panning_source);
+                                            setter.set_parameter(&params.panning_source, source);
+                                            setter.end_set_parameter(&params.panning_source);
+                                        }
+                                    }
+                                });
+
                                 ui.horizontal(|ui| {
                                     if ui
                                         .add(egui::widgets::SelectableLabel::new(
                                             *panning_mode == PanningMode::Linear,
-                                            "Linear",
+                                            "Linear (-6 dB)",
                                         ))
                                         .clicked()
                                     {
@@ -605,7 +4176,7 @@ impl Plugin for HackAudio {
                                     if ui
                                         .add(egui::widgets::SelectableLabel::new(
                                             *panning_mode == PanningMode::Square,
-                                            "Square",
+                                            "Square (-3 dB)",
                                         ))
                                         .clicked()
                                     {
@@ -619,7 +4190,7 @@ impl Plugin for HackAudio {
                                     if ui
                                         .add(egui::widgets::SelectableLabel::new(
                                             *panning_mode == PanningMode::Sine,
-                                            "Sine",
+                                            "Sine (-3 dB)",
                                         ))
                                         .clicked()
                                     {
@@ -628,10 +4199,45 @@ impl Plugin for HackAudio {
                                             .set_parameter(&params.panning_mode, PanningMode::Sine);
                                         setter.end_set_parameter(&params.panning_mode);
                                     }
+                                    if ui
+                                        .add(egui::widgets::SelectableLabel::new(
+                                            *panning_mode == PanningMode::Compromise,
+                                            "Compromise (-4.5 dB)",
+                                        ))
+                                        .clicked()
+                                    {
+                                        setter.begin_set_parameter(&params.panning_mode);
+                                        setter.set_parameter(
+                                            &params.panning_mode,
+                                            PanningMode::Compromise,
+                                        );
+                                        setter.end_set_parameter(&params.panning_mode);
+                                    }
                                 });
 
                                 ui.label("Pan");
                                 ui.add(widgets::ParamSlider::for_param(&params.pan, setter));
+
+                                if params.panning_source.value() == PanningSource::Stereo {
+                                    ui.label("Width");
+                                    ui.add(widgets::ParamSlider::for_param(
+                                        &params.stereo_pan_width,
+                                        setter,
+                                    ));
+                                }
+
+                                if params.panning_source.value() == PanningSource::Binaural {
+                                    ui.label("Azimuth");
+                                    ui.add(widgets::ParamSlider::for_param(
+                                        &params.binaural_azimuth,
+                                        setter,
+                                    ));
+                                    ui.label("Elevation");
+                                    ui.add(widgets::ParamSlider::for_param(
+                                        &params.binaural_elevation,
+                                        setter,
+                                    ));
+                                }
                             }
                             Fx::MidSideEncode => {
                                 ui.label("MidSideEncode");
@@ -722,12 +4328,60 @@ impl Plugin for HackAudio {
                                 ui.label("Distortion");
                                 ui.separator();
 
+                                ui.label("Drive (dB)");
+                                ui.horizontal(|ui| {
+                                    ui.add(widgets::ParamSlider::for_param(
+                                        &params.distortion_drive,
+                                        setter,
+                                    ));
+
+                                    let auto_gain = params.distortion_auto_gain.value();
+                                    if ui
+                                        .add(egui::widgets::SelectableLabel::new(
+                                            auto_gain,
+                                            "Auto Gain",
+                                        ))
+                                        .clicked()
+                                    {
+                                        setter.begin_set_parameter(&params.distortion_auto_gain);
+                                        setter.set_parameter(
+                                            &params.distortion_auto_gain,
+                                            !auto_gain,
+                                        );
+                                        setter.end_set_parameter(&params.distortion_auto_gain);
+                                    }
+                                });
+
                                 ui.label("Distortion Amount");
                                 ui.add(widgets::ParamSlider::for_param(
                                     &params.distortion_amount,
                                     setter,
                                 ));
 
+                                ui.label("Bias");
+                                ui.add(widgets::ParamSlider::for_param(
+                                    &params.distortion_bias,
+                                    setter,
+                                ));
+
+                                ui.label("Emphasis (dB)");
+                                ui.add(widgets::ParamSlider::for_param(
+                                    &params.distortion_emphasis,
+                                    setter,
+                                ));
+
+                                ui.label("Tone (dB)");
+                                ui.add(widgets::ParamSlider::for_param(
+                                    &params.distortion_tone,
+                                    setter,
+                                ));
+
+                                ui.label("Output (dB)");
+                                ui.add(widgets::ParamSlider::for_param(
+                                    &params.distortion_output,
+                                    setter,
+                                ));
+
                                 ui.label("Distortion Mix");
                                 ui.add(widgets::ParamSlider::for_param(
                                     &params.distortion_mix,
@@ -740,6 +4394,180 @@ impl Plugin for HackAudio {
                                     setter,
                                 ));
 
+                                ui.label("Bit Crush Rate (Hz)");
+                                ui.horizontal(|ui| {
+                                    ui.add(widgets::ParamSlider::for_param(
+                                        &params.bit_crush_rate,
+                                        setter,
+                                    ));
+
+                                    let prefilter = params.bit_crush_prefilter.value();
+                                    if ui
+                                        .add(egui::widgets::SelectableLabel::new(
+                                            prefilter,
+                                            "Anti-Alias Prefilter",
+                                        ))
+                                        .clicked()
+                                    {
+                                        setter.begin_set_parameter(&params.bit_crush_prefilter);
+                                        setter
+                                            .set_parameter(&params.bit_crush_prefilter, !prefilter);
+                                        setter.end_set_parameter(&params.bit_crush_prefilter);
+                                    }
+                                });
+
+                                ui.label("Bit Crush Jitter");
+                                ui.add(widgets::ParamSlider::for_param(
+                                    &params.bit_crush_jitter,
+                                    setter,
+                                ));
+
+                                if matches!(
+                                    distortion_mode,
+                                    DistortionMode::SineFold | DistortionMode::TriangleFold
+                                ) {
+                                    ui.label("Folds");
+                                    ui.add(widgets::ParamSlider::for_param(
+                                        &params.fold_count,
+                                        setter,
+                                    ));
+                                }
+
+                                if *distortion_mode == DistortionMode::Triode {
+                                    ui.label("Triode Bias");
+                                    ui.add(widgets::ParamSlider::for_param(
+                                        &params.triode_bias,
+                                        setter,
+                                    ));
+                                }
+
+                                if *distortion_mode == DistortionMode::Tape {
+                                    ui.label("Tape Hysteresis");
+                                    ui.add(widgets::ParamSlider::for_param(
+                                        &params.tape_hysteresis,
+                                        setter,
+                                    ));
+
+                                    ui.label("Tape Speed");
+                                    ui.horizontal(|ui| {
+                                        let tape_speed = params.tape_speed.value();
+                                        for (speed, label) in [
+                                            (TapeSpeed::Ips7_5, "7.5 ips"),
+                                            (TapeSpeed::Ips15, "15 ips"),
+                                            (TapeSpeed::Ips30, "30 ips"),
+                                        ] {
+                                            if ui
+                                                .add(egui::widgets::SelectableLabel::new(
+                                                    tape_speed == speed,
+                                                    label,
+                                                ))
+                                                .clicked()
+                                            {
+                                                setter.begin_set_parameter(&params.tape_speed);
+                                                setter.set_parameter(&params.tape_speed, speed);
+                                                setter.end_set_parameter(&params.tape_speed);
+                                            }
+                                        }
+                                    });
+                                }
+
+                                if let Some(antialiasing_param) =
+                                    params.antialiasing_param(distortion_mode)
+                                {
+                                    ui.label("Antialiasing");
+                                    ui.horizontal(|ui| {
+                                        let antialiasing = antialiasing_param.value();
+                                        for (order, label) in [
+                                            (Antialiasing::Off, "Off"),
+                                            (Antialiasing::FirstOrder, "ADAA 1st Order"),
+                                            (Antialiasing::SecondOrder, "ADAA 2nd Order"),
+                                        ] {
+                                            if ui
+                                                .add(egui::widgets::SelectableLabel::new(
+                                                    antialiasing == order,
+                                                    label,
+                                                ))
+                                                .clicked()
+                                            {
+                                                setter.begin_set_parameter(antialiasing_param);
+                                                setter.set_parameter(antialiasing_param, order);
+                                                setter.end_set_parameter(antialiasing_param);
+                                            }
+                                        }
+                                    });
+                                }
+
+                                ui.label("Oversampling");
+                                ui.horizontal(|ui| {
+                                    let oversampling = params.distortion_oversampling.value();
+                                    for (factor, label) in [
+                                        (Oversampling::X1, "Off"),
+                                        (Oversampling::X2, "2x"),
+                                        (Oversampling::X4, "4x"),
+                                        (Oversampling::X8, "8x"),
+                                        (Oversampling::X16, "16x"),
+                                    ] {
+                                        if ui
+                                            .add(egui::widgets::SelectableLabel::new(
+                                                oversampling == factor,
+                                                label,
+                                            ))
+                                            .clicked()
+                                        {
+                                            setter.begin_set_parameter(
+                                                &params.distortion_oversampling,
+                                            );
+                                            setter.set_parameter(
+                                                &params.distortion_oversampling,
+                                                factor,
+                                            );
+                                            setter
+                                                .end_set_parameter(&params.distortion_oversampling);
+                                        }
+                                    }
+                                });
+
+                                ui.label("Bands");
+                                ui.add(widgets::ParamSlider::for_param(
+                                    &params.distortion_band_count,
+                                    setter,
+                                ));
+
+                                let band_count = params.distortion_band_count();
+                                if band_count > 1 {
+                                    for (crossover, label) in [
+                                        (&params.distortion_crossover_low, "Low Crossover"),
+                                        (&params.distortion_crossover_mid, "Mid Crossover"),
+                                        (&params.distortion_crossover_high, "High Crossover"),
+                                    ]
+                                    .into_iter()
+                                    .take(band_count - 1)
+                                    {
+                                        ui.label(label);
+                                        ui.add(widgets::ParamSlider::for_param(crossover, setter));
+                                    }
+
+                                    for (band, band_params) in
+                                        params.distortion_bands.iter().take(band_count).enumerate()
+                                    {
+                                        ui.label(format!("Band {}: Mode, Drive, Mix", band + 1));
+                                        ui.horizontal(|ui| {
+                                            ui.add(widgets::ParamSlider::for_param(
+                                                &band_params.mode,
+                                                setter,
+                                            ));
+                                            ui.add(widgets::ParamSlider::for_param(
+                                                &band_params.drive,
+                                                setter,
+                                            ));
+                                            ui.add(widgets::ParamSlider::for_param(
+                                                &band_params.mix,
+                                                setter,
+                                            ));
+                                        });
+                                    }
+                                }
+
                                 ui.horizontal_wrapped(|ui| {
                                     if ui
                                         .add(egui::widgets::SelectableLabel::new(
@@ -840,80 +4668,308 @@ impl Plugin for HackAudio {
                                         );
                                         setter.end_set_parameter(&params.distortion_mode);
                                     }
+                                    if ui
+                                        .add(egui::widgets::SelectableLabel::new(
+                                            *distortion_mode == DistortionMode::Custom,
+                                            "Custom",
+                                        ))
+                                        .clicked()
+                                    {
+                                        setter.begin_set_parameter(&params.distortion_mode);
+                                        setter.set_parameter(
+                                            &params.distortion_mode,
+                                            DistortionMode::Custom,
+                                        );
+                                        setter.end_set_parameter(&params.distortion_mode);
+                                    }
+                                    if ui
+                                        .add(egui::widgets::SelectableLabel::new(
+                                            *distortion_mode == DistortionMode::Triode,
+                                            "Triode",
+                                        ))
+                                        .clicked()
+                                    {
+                                        setter.begin_set_parameter(&params.distortion_mode);
+                                        setter.set_parameter(
+                                            &params.distortion_mode,
+                                            DistortionMode::Triode,
+                                        );
+                                        setter.end_set_parameter(&params.distortion_mode);
+                                    }
+                                    if ui
+                                        .add(egui::widgets::SelectableLabel::new(
+                                            *distortion_mode == DistortionMode::Tape,
+                                            "Tape",
+                                        ))
+                                        .clicked()
+                                    {
+                                        setter.begin_set_parameter(&params.distortion_mode);
+                                        setter.set_parameter(
+                                            &params.distortion_mode,
+                                            DistortionMode::Tape,
+                                        );
+                                        setter.end_set_parameter(&params.distortion_mode);
+                                    }
+                                    if ui
+                                        .add(egui::widgets::SelectableLabel::new(
+                                            *distortion_mode == DistortionMode::SineFold,
+                                            "Sine Fold",
+                                        ))
+                                        .clicked()
+                                    {
+                                        setter.begin_set_parameter(&params.distortion_mode);
+                                        setter.set_parameter(
+                                            &params.distortion_mode,
+                                            DistortionMode::SineFold,
+                                        );
+                                        setter.end_set_parameter(&params.distortion_mode);
+                                    }
+                                    if ui
+                                        .add(egui::widgets::SelectableLabel::new(
+                                            *distortion_mode == DistortionMode::TriangleFold,
+                                            "Triangle Fold",
+                                        ))
+                                        .clicked()
+                                    {
+                                        setter.begin_set_parameter(&params.distortion_mode);
+                                        setter.set_parameter(
+                                            &params.distortion_mode,
+                                            DistortionMode::TriangleFold,
+                                        );
+                                        setter.end_set_parameter(&params.distortion_mode);
+                                    }
+                                    if ui
+                                        .add(egui::widgets::SelectableLabel::new(
+                                            *distortion_mode == DistortionMode::HalfWaveRectifier,
+                                            "Half Wave Rectifier",
+                                        ))
+                                        .clicked()
+                                    {
+                                        setter.begin_set_parameter(&params.distortion_mode);
+                                        setter.set_parameter(
+                                            &params.distortion_mode,
+                                            DistortionMode::HalfWaveRectifier,
+                                        );
+                                        setter.end_set_parameter(&params.distortion_mode);
+                                    }
+                                    if ui
+                                        .add(egui::widgets::SelectableLabel::new(
+                                            *distortion_mode == DistortionMode::FullWaveRectifier,
+                                            "Full Wave Rectifier",
+                                        ))
+                                        .clicked()
+                                    {
+                                        setter.begin_set_parameter(&params.distortion_mode);
+                                        setter.set_parameter(
+                                            &params.distortion_mode,
+                                            DistortionMode::FullWaveRectifier,
+                                        );
+                                        setter.end_set_parameter(&params.distortion_mode);
+                                    }
                                 }); // horizontal UI end
+
+                                if *distortion_mode == DistortionMode::Custom {
+                                    custom_curve_editor(ui, &params);
+                                }
+
+                                distortion_visualization(ui, &params, &mut state.distortion);
                             }
                             Fx::Goniometer => {
                                 ui.label("Stereo Visualizer (Goniometer)");
-                                // TODO - draw goniometer component here
-                                // and then read data from the goniometer buffer into the component
-                                let axis_color = Color32::from_additive_luminance(196);
+                                goniometer_view(
+                                    ui,
+                                    &mut view_settings,
+                                    &goniometer,
+                                    &stereo_meter,
+                                    &mut state.goniometer,
+                                );
+                            }
+                            Fx::Analyzer => {
+                                ui.label("Spectrum Analyzer");
+                                spectrum_view(
+                                    ui,
+                                    &mut view_settings,
+                                    &spectrum,
+                                    &mut state.spectrum,
+                                );
+                            }
+                            Fx::Monitor => {
+                                ui.label("Monitor");
 
-                                let desired_size = ui.available_width() * vec2(0.8, 0.8);
-                                let (_id, rect) = ui.allocate_space(desired_size);
-                                // let mut shapes = vec![];
+                                ui.horizontal(|ui| {
+                                    let monitor_mode = params.monitor_mode.value();
+                                    for (mode, label) in [
+                                        (MonitorMode::Stereo, "Stereo"),
+                                        (MonitorMode::Mid, "Mid"),
+                                        (MonitorMode::Side, "Side"),
+                                        (MonitorMode::Swap, "L/R Swap"),
+                                        (MonitorMode::Mono, "Mono"),
+                                    ] {
+                                        if ui
+                                            .add(egui::widgets::SelectableLabel::new(
+                                                monitor_mode == mode,
+                                                label,
+                                            ))
+                                            .clicked()
+                                        {
+                                            setter.begin_set_parameter(&params.monitor_mode);
+                                            setter.set_parameter(&params.monitor_mode, mode);
+                                            setter.end_set_parameter(&params.monitor_mode);
+                                        }
+                                    }
+                                });
 
-                                let to_screen = emath::RectTransform::from_to(
-                                    Rect::from_x_y_ranges(-1.0..=1.0, -1.0..=1.0),
-                                    rect,
-                                );
+                                ui.horizontal(|ui| {
+                                    for (param, label) in [
+                                        (&params.monitor_invert_left, "Invert L"),
+                                        (&params.monitor_invert_right, "Invert R"),
+                                    ] {
+                                        let inverted = param.value();
+                                        if ui
+                                            .add(egui::widgets::SelectableLabel::new(
+                                                inverted, label,
+                                            ))
+                                            .clicked()
+                                        {
+                                            setter.begin_set_parameter(param);
+                                            setter.set_parameter(param, !inverted);
+                                            setter.end_set_parameter(param);
+                                        }
+                                    }
+                                });
+                            }
+                            Fx::Imager => {
+                                ui.label("Stereo Imager");
 
-                                // Draw the measurement axis
-                                let top_left = rect.left_top();
-                                let top_right = rect.right_top();
-                                let bottom_left = rect.left_bottom();
-                                let bottom_right = rect.right_bottom();
-                                let center_top = rect.center_top();
-                                let center_bottom = rect.center_bottom();
-
-                                ui.painter().line_segment(
-                                    [top_left, bottom_right],
-                                    Stroke::new(1.0, axis_color),
-                                );
+                                ui.label("Bands");
+                                ui.add(widgets::ParamSlider::for_param(
+                                    &params.imager_band_count,
+                                    setter,
+                                ));
 
-                                ui.painter().line_segment(
-                                    [top_right, bottom_left],
-                                    Stroke::new(1.0, axis_color),
-                                );
+                                let band_count = params.imager_band_count.value() as usize;
+                                for (crossover, label) in [
+                                    (&params.imager_crossover_low, "Low Crossover"),
+                                    (&params.imager_crossover_mid, "Mid Crossover"),
+                                    (&params.imager_crossover_high, "High Crossover"),
+                                ]
+                                .into_iter()
+                                .take(band_count - 1)
+                                {
+                                    ui.label(label);
+                                    ui.add(widgets::ParamSlider::for_param(crossover, setter));
+                                }
 
-                                ui.painter().line_segment(
-                                    [center_top, center_bottom],
-                                    Stroke::new(1.0, axis_color),
-                                );
+                                for (band, band_params) in
+                                    params.imager_bands.iter().take(band_count).enumerate()
+                                {
+                                    ui.label(format!("Band {} Width", band + 1));
+                                    ui.add(widgets::ParamSlider::for_param(
+                                        &band_params.width,
+                                        setter,
+                                    ));
+                                }
 
-                                ui.painter().circle_stroke(
-                                    rect.center(),
-                                    rect.width() / 2.0,
-                                    Stroke::new(1.0, axis_color),
-                                );
+                                ui.separator();
 
+                                let bass_mono = params.imager_bass_mono.value();
+                                if ui
+                                    .add(egui::widgets::SelectableLabel::new(
+                                        bass_mono,
+                                        "Bass Mono",
+                                    ))
+                                    .clicked()
                                 {
-                                    // TODO - I'd like to pull the shape map
-                                    // and paint out of the lock. I should only be locking the read
-                                    // and then unlocking after the data is now available
-                                    let lock = goniometer.try_lock();
-                                    if let Ok(mut mutex) = lock {
-                                        let data = mutex.read();
-
-                                        let shapes = data
-                                            .left
-                                            .into_iter()
-                                            .zip(data.right)
-                                            .map(|(left, right)| {
-                                                let pos = Pos2 { x: left, y: right };
-                                                let screen_center = to_screen * pos;
-                                                egui::epaint::Shape::circle_filled(
-                                                    screen_center,
-                                                    1.0,
-                                                    Color32::LIGHT_BLUE,
-                                                )
-                                            })
-                                            .collect::<Vec<egui::epaint::Shape>>();
-
-                                        ui.painter().extend(shapes);
+                                    setter.begin_set_parameter(&params.imager_bass_mono);
+                                    setter.set_parameter(&params.imager_bass_mono, !bass_mono);
+                                    setter.end_set_parameter(&params.imager_bass_mono);
+                                }
+
+                                if bass_mono {
+                                    ui.label("Mono Below");
+                                    ui.add(widgets::ParamSlider::for_param(
+                                        &params.imager_bass_mono_frequency,
+                                        setter,
+                                    ));
+                                }
+                            }
+                            Fx::Haas => {
+                                ui.label("Haas");
+
+                                ui.horizontal(|ui| {
+                                    let haas_channel = params.haas_channel.value();
+                                    for (channel, label) in [
+                                        (HaasChannel::Left, "Delay Left"),
+                                        (HaasChannel::Right, "Delay Right"),
+                                    ] {
+                                        if ui
+                                            .add(egui::widgets::SelectableLabel::new(
+                                                haas_channel == channel,
+                                                label,
+                                            ))
+                                            .clicked()
+                                        {
+                                            setter.begin_set_parameter(&params.haas_channel);
+                                            setter.set_parameter(&params.haas_channel, channel);
+                                            setter.end_set_parameter(&params.haas_channel);
+                                        }
                                     }
+                                });
+
+                                ui.label("Delay");
+                                ui.add(widgets::ParamSlider::for_param(&params.haas_delay, setter));
+
+                                ui.label("Delayed Level").on_hover_text(
+                                    "Raise to pull the image back towards the center",
+                                );
+                                ui.add(widgets::ParamSlider::for_param(&params.haas_level, setter));
+
+                                let haas_filter = params.haas_filter.value();
+                                if ui
+                                    .add(egui::widgets::SelectableLabel::new(
+                                        haas_filter,
+                                        "Filter Delayed Channel",
+                                    ))
+                                    .clicked()
+                                {
+                                    setter.begin_set_parameter(&params.haas_filter);
+                                    setter.set_parameter(&params.haas_filter, !haas_filter);
+                                    setter.end_set_parameter(&params.haas_filter);
+                                }
+
+                                if haas_filter {
+                                    ui.label("Highpass");
+                                    ui.add(widgets::ParamSlider::for_param(
+                                        &params.haas_highpass,
+                                        setter,
+                                    ));
+                                    ui.label("Lowpass");
+                                    ui.add(widgets::ParamSlider::for_param(
+                                        &params.haas_lowpass,
+                                        setter,
+                                    ));
+                                }
+
+                                ui.separator();
+
+                                // Summing a signal with a delayed copy of itself comb filters, which
+                                // shows up here as the correlation heading towards zero or below
+                                if let Ok(mut readout) = haas_meter.try_lock() {
+                                    let correlation = readout.read().correlation;
+                                    ui.label(format!("Mono Compatibility: {correlation:+.2}"));
+                                    let correlation_color = if correlation < 0.0 {
+                                        Color32::LIGHT_RED
+                                    } else {
+                                        Color32::LIGHT_GREEN
+                                    };
+                                    stereo_meter_bar(ui, correlation, correlation_color);
                                 }
                             }
                         });
+
+                        if view_settings != previous_view_settings {
+                            *params.view_settings.write() = view_settings;
+                        }
                     });
             },
         )
@@ -926,9 +4982,28 @@ impl Plugin for HackAudio {
         _context: &mut impl InitContext<Self>,
     ) -> bool {
         self.host_sample_rate = buffer_config.sample_rate;
+        self.loudness_meter
+            .set_sample_rate(buffer_config.sample_rate);
+        self.oscilloscope_input
+            .resize(buffer_config.max_buffer_size as usize, 0.0);
+        self.mid_side_wrapper
+            .initialize(buffer_config.max_buffer_size as usize);
+        self.haas.initialize(buffer_config.sample_rate);
+
+        let voice_count = self.params.chorus_voice_count.value() as usize;
+        for chorus in self.chorus.iter_mut() {
+            chorus.initialize_lfos(voice_count);
+        }
+
+        self.view_settings = *self.params.view_settings.read();
 
-        let voice_count = self.params.chorus_voice_count.value() as usize;
-        self.chorus.initialize_lfos(voice_count);
+        // The curve points may just have been restored from a preset
+        let mut custom_curve = self.params.custom_curve.write();
+        sanitize_custom_curve(&mut custom_curve);
+        self.distortion.custom_curve.bake(&custom_curve);
+        self.params
+            .custom_curve_changed
+            .store(false, Ordering::Relaxed);
 
         true
     }
@@ -937,76 +5012,1402 @@ impl Plugin for HackAudio {
         &mut self,
         buffer: &mut Buffer,
         _aux: &mut AuxiliaryBuffers,
-        _context: &mut impl ProcessContext<Self>,
+        context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
         let selected_fx = self.params.selected_fx.value();
         let sample_rate = self.host_sample_rate;
 
-        match selected_fx {
-            Fx::Panning => panning_plugin_process(buffer, &self.params),
+        // The settings from the previous block are fine whenever the editor is busy writing them
+        if let Some(view_settings) = self.params.view_settings.try_read() {
+            self.view_settings = *view_settings;
+        }
+
+        // Oversampling delays the distortion output, so the host needs to compensate for it
+        let latency_samples = match selected_fx {
+            Fx::Distortion => self.params.distortion_latency(),
+            _ => 0,
+        };
+        if latency_samples != self.latency_samples {
+            self.latency_samples = latency_samples;
+            context.set_latency_samples(latency_samples);
+        }
+
+        let metering = self.params.editor_state.is_open();
+        if metering {
+            self.input_detector.process(
+                sample_rate,
+                buffer.as_slice_immutable(),
+                &self.input_meter,
+            );
+        }
+
+        let oscilloscope = metering && self.view_settings.oscilloscope;
+        let oscilloscope_samples = buffer.samples().min(self.oscilloscope_input.len());
+        if oscilloscope {
+            let channels = buffer.as_slice_immutable();
+            for (sample_idx, input) in self.oscilloscope_input[..oscilloscope_samples]
+                .iter_mut()
+                .enumerate()
+            {
+                *input = mono_sample(channels, sample_idx);
+            }
+        }
+
+        // The overlay only costs anything while someone can actually see it
+        let overlay_tap = match selected_fx {
+            Fx::Goniometer => None,
+            _ if self.view_settings.goniometer_overlay && self.params.editor_state.is_open() => {
+                Some(self.view_settings.goniometer_tap)
+            }
+            _ => None,
+        };
+
+        if overlay_tap == Some(AnalyzerTap::Pre) {
+            self.goniometer_process(buffer);
+        }
+
+        let mid_side = self.params.mid_side_processing.value()
+            && selected_fx.supports_mid_side()
+            && buffer.channels() >= 2;
+        let mid_side_samples = self.mid_side_wrapper.block_size(buffer.samples());
+        if mid_side {
+            self.mid_side_wrapper
+                .encode(buffer.as_slice(), mid_side_samples);
+        }
+
+        // The delay lines still hold whatever played the last time the widener was selected
+        if selected_fx == Fx::Haas && self.previous_fx != Fx::Haas {
+            self.haas.reset();
+        }
+        self.previous_fx = selected_fx;
+
+        let status = match selected_fx {
+            Fx::Panning => {
+                panning_plugin_process(sample_rate, buffer, &self.params, &mut self.binaural)
+            }
             Fx::MidSideEncode => mid_side_encode_plugin_process(buffer, &self.params),
             Fx::MidSideDecode => mid_side_decode_plugin_process(buffer, &self.params),
-            Fx::Goniometer => {
-                goniometer_plugin_process(buffer, &self.params, &mut self.goniometer_input)
+            Fx::Goniometer => self.goniometer_process(buffer),
+            Fx::Distortion => distortion_plugin_process(
+                sample_rate,
+                buffer,
+                &self.params,
+                mid_side,
+                &mut self.distortion,
+            ),
+            Fx::Delay => delay_plugin_process(sample_rate, buffer, &self.params, mid_side, &mut self.delay_buffer),
+            Fx::Chorus => chorus_plugin_process(sample_rate, buffer, &self.params, mid_side, &mut self.chorus),
+            Fx::Analyzer => analyzer_plugin_process(
+                sample_rate,
+                buffer,
+                &self.params,
+                &self.view_settings,
+                &mut self.spectrum_analyzer,
+                &mut self.spectrum_input,
+                &mut self.spectrogram_input,
+            ),
+            Fx::Monitor => monitor_plugin_process(buffer, &self.params),
+            Fx::Imager => {
+                imager_plugin_process(sample_rate, buffer, &self.params, &mut self.imager)
             }
-            Fx::Distortion => distortion_plugin_process(buffer, &self.params),
-            Fx::Delay => delay_plugin_process(sample_rate, buffer, &self.params, &mut self.delay_buffer),
-            Fx::Chorus => chorus_plugin_process(sample_rate, buffer, &self.params, &mut self.chorus),
+            Fx::Haas => haas_plugin_process(
+                sample_rate,
+                buffer,
+                &self.params,
+                &self.view_settings,
+                &mut self.haas,
+                &mut self.haas_meter_input,
+            ),
+        };
+
+        if mid_side {
+            self.mid_side_wrapper.decode(
+                buffer.as_slice(),
+                mid_side_samples,
+                latency_samples as usize,
+                [
+                    self.params.mid_side_mid_mix.value() / 100.0,
+                    self.params.mid_side_side_mix.value() / 100.0,
+                ],
+                [
+                    db_to_gain(self.params.mid_side_mid_gain.value()),
+                    db_to_gain(self.params.mid_side_side_gain.value()),
+                ],
+            );
+        }
+
+        if overlay_tap == Some(AnalyzerTap::Post) {
+            self.goniometer_process(buffer);
+        }
+
+        // Outside of the analyzer itself the spectrogram looks at the effect's output
+        if self.view_settings.spectrogram && selected_fx != Fx::Analyzer {
+            analyzer_plugin_process(
+                sample_rate,
+                buffer,
+                &self.params,
+                &self.view_settings,
+                &mut self.spectrum_analyzer,
+                &mut self.spectrum_input,
+                &mut self.spectrogram_input,
+            );
+        }
+
+        if oscilloscope {
+            self.oscilloscope.push(
+                sample_rate,
+                latency_samples,
+                &self.oscilloscope_input[..oscilloscope_samples],
+                buffer.as_slice_immutable(),
+            );
+        }
+
+        if metering {
+            self.output_detector.process(
+                sample_rate,
+                buffer.as_slice_immutable(),
+                &self.output_meter,
+            );
+        }
+
+        // Keeps measuring with the editor closed, otherwise the integrated loudness would miss
+        // whatever played in the meantime
+        if self.view_settings.loudness_meter {
+            loudness_meter_process(
+                buffer.as_slice_immutable(),
+                &self.params,
+                &mut self.loudness_meter,
+                &mut self.loudness_input,
+            );
+        }
+
+        status
+    }
+}
+
+impl HackAudio {
+    /// Feeds the goniometer and its meters without touching the audio.
+    fn goniometer_process(&mut self, buffer: &mut Buffer) -> ProcessStatus {
+        goniometer_plugin_process(
+            self.host_sample_rate,
+            buffer,
+            &self.view_settings,
+            &self.goniometer,
+            &mut self.stereo_meter,
+            &mut self.stereo_meter_input,
+        )
+    }
+}
+
+/// Measures the loudness of whatever the selected effect put out.
+pub fn loudness_meter_process(
+    channels: &[&mut [f32]],
+    params: &PluginParams,
+    loudness_meter: &mut LoudnessMeter,
+    loudness_input: &mut triple_buffer::Input<LoudnessReadout>,
+) {
+    if params.loudness_reset.swap(false, Ordering::Relaxed) {
+        loudness_meter.reset();
+        loudness_input.write(LoudnessReadout::default());
+    }
+
+    let Some(left_channel) = channels.first() else {
+        return;
+    };
+
+    for (sample_idx, &left) in left_channel.iter().enumerate() {
+        let right = channels.get(1).map(|right| right[sample_idx]);
+        if let Some(readout) = loudness_meter.process(left, right) {
+            loudness_input.write(readout);
         }
     }
 }
 
 pub fn goniometer_plugin_process(
+    sample_rate: f32,
     buffer: &mut Buffer,
-    _params: &Arc<PluginParams>,
-    goniometer_input: &mut triple_buffer::Input<OutputBuffer>,
+    view_settings: &ViewSettings,
+    goniometer: &SampleRing,
+    stereo_meter: &mut StereoMeter,
+    stereo_meter_input: &mut triple_buffer::Input<StereoMeterReadout>,
 ) -> ProcessStatus {
-    let mut ui_buffer = OutputBuffer::default();
     let num_samples = buffer.samples();
     let output = buffer.as_slice();
 
+    // There's no stereo image to show with the mono layout
+    if output.len() < 2 {
+        return ProcessStatus::Normal;
+    }
+
+    stereo_meter.set_integration_time(sample_rate, view_settings.stereo_meter_integration);
+
     for sample_idx in 0..num_samples {
         let l = output[0][sample_idx];
         let r = output[1][sample_idx];
+        stereo_meter.process(l, r);
         let radius = ((l * l) + (r * r)).sqrt();
         let angle = r.atan2(l) + (std::f32::consts::PI / 4.0);
 
-        ui_buffer.left[sample_idx] = radius * angle.cos();
-        ui_buffer.right[sample_idx] = radius * angle.sin();
+        goniometer.push(radius * angle.cos(), radius * angle.sin());
+    }
+
+    stereo_meter_input.write(stereo_meter.readout());
+
+    ProcessStatus::Normal
+}
+
+/// Passes the audio through untouched and feeds the spectrum analyzer.
+pub fn analyzer_plugin_process(
+    sample_rate: f32,
+    buffer: &mut Buffer,
+    params: &Arc<PluginParams>,
+    view_settings: &ViewSettings,
+    spectrum_analyzer: &mut SpectrumAnalyzer,
+    spectrum_input: &mut triple_buffer::Input<SpectrumFrame>,
+    spectrogram_input: &mut triple_buffer::Input<SpectrumFrame>,
+) -> ProcessStatus {
+    // The FFTs aren't free, and there's nobody to show them to with the editor closed
+    if !params.editor_state.is_open() {
+        return ProcessStatus::Normal;
     }
 
-    goniometer_input.write(ui_buffer);
+    let num_samples = buffer.samples();
+    let output = buffer.as_slice();
+    let settings = SpectrumSettings {
+        fft_size: view_settings.analyzer_fft_size.samples(),
+        window: view_settings.analyzer_window,
+        averaging_ms: view_settings.analyzer_averaging,
+        channels: view_settings.analyzer_view.channels(),
+    };
+
+    for sample_idx in 0..num_samples {
+        let left = output[0][sample_idx];
+        let right = output.get(1).map_or(left, |right| right[sample_idx]);
+        if let Some(frame) = spectrum_analyzer.process(left, right, sample_rate, &settings) {
+            spectrum_input.write(frame);
+            if view_settings.spectrogram {
+                spectrogram_input.write(*spectrum_analyzer.unaveraged());
+            }
+        }
+    }
 
     ProcessStatus::Normal
 }
 
+/// The goniometer with its correlation and balance meters. Used by the stereo visualizer tab and
+/// by the overlay that can be shown next to every other effect.
+pub fn goniometer_view(
+    ui: &mut egui::Ui,
+    view_settings: &mut ViewSettings,
+    goniometer: &SampleRing,
+    stereo_meter: &Mutex<triple_buffer::Output<StereoMeterReadout>>,
+    display: &mut GoniometerDisplay,
+) {
+    let axis_color = Color32::from_additive_luminance(196);
+
+    let desired_size = ui.available_width() * vec2(0.8, 0.8);
+    let (_id, rect) = ui.allocate_space(desired_size);
+
+    let elapsed = ui.input(|input| input.stable_dt);
+    display.decay(elapsed, view_settings.goniometer_persistence);
+    display.draw(
+        goniometer,
+        view_settings.goniometer_rendering,
+        view_settings.goniometer_auto_gain,
+    );
+
+    let texture = display.texture(ui.ctx(), view_settings.goniometer_heatmap);
+    ui.painter().image(
+        texture.id(),
+        rect,
+        Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+        Color32::WHITE,
+    );
+
+    // Draw the measurement axis
+    let top_left = rect.left_top();
+    let top_right = rect.right_top();
+    let bottom_left = rect.left_bottom();
+    let bottom_right = rect.right_bottom();
+    let center_top = rect.center_top();
+    let center_bottom = rect.center_bottom();
+
+    ui.painter()
+        .line_segment([top_left, bottom_right], Stroke::new(1.0, axis_color));
+
+    ui.painter()
+        .line_segment([top_right, bottom_left], Stroke::new(1.0, axis_color));
+
+    ui.painter()
+        .line_segment([center_top, center_bottom], Stroke::new(1.0, axis_color));
+
+    ui.painter().circle_stroke(
+        rect.center(),
+        rect.width() / 2.0,
+        Stroke::new(1.0, axis_color),
+    );
+
+    ui.horizontal(|ui| {
+        for (style, label) in [
+            (GoniometerRendering::Dots, "Dots"),
+            (GoniometerRendering::Lines, "Lines"),
+        ] {
+            ui.selectable_value(&mut view_settings.goniometer_rendering, style, label);
+        }
+
+        ui.separator();
+
+        ui.toggle_value(&mut view_settings.goniometer_heatmap, "Heatmap");
+        ui.toggle_value(&mut view_settings.goniometer_auto_gain, "Auto Gain");
+    });
+
+    ui.label("Persistence");
+    ui.add(
+        egui::Slider::new(&mut view_settings.goniometer_persistence, 0.0..=3_000.0)
+            .logarithmic(true)
+            .suffix(" ms")
+            .fixed_decimals(0),
+    );
+
+    if let Ok(mut readout) = stereo_meter.try_lock() {
+        let readout = *readout.read();
+
+        ui.label(format!("Correlation: {:+.2}", readout.correlation));
+        let correlation_color = if readout.correlation < 0.0 {
+            Color32::LIGHT_RED
+        } else {
+            Color32::LIGHT_GREEN
+        };
+        stereo_meter_bar(ui, readout.correlation, correlation_color);
+
+        ui.label(format!("Balance: {:+.2}", readout.balance));
+        stereo_meter_bar(ui, readout.balance, Color32::LIGHT_BLUE);
+    }
+
+    ui.label("Meter Integration Time");
+    ui.add(
+        egui::Slider::new(&mut view_settings.stereo_meter_integration, 10.0..=3_000.0)
+            .logarithmic(true)
+            .suffix(" ms")
+            .fixed_decimals(0),
+    );
+}
+
+// Resolution of the goniometer's phosphor texture
+const GONIOMETER_RESOLUTION: usize = 256;
+
+/// Editor side state of the goniometer. Samples are accumulated into an intensity map that fades
+/// out over time like the phosphor of an analog scope, and the map is uploaded as a single texture
+/// rather than painting every sample as its own shape.
+#[derive(Clone)]
+pub struct GoniometerDisplay {
+    intensity: Vec<f32>,
+    gain: f32,
+    texture: Option<egui::TextureHandle>,
+    /// Where in the [`SampleRing`] the previous frame stopped reading.
+    read_position: usize,
+    /// The last point drawn, so lines continue where the previous frame left off.
+    previous: Option<Pos2>,
+    left: Vec<f32>,
+    right: Vec<f32>,
+}
+
+impl Default for GoniometerDisplay {
+    fn default() -> Self {
+        Self {
+            intensity: vec![0.0; GONIOMETER_RESOLUTION * GONIOMETER_RESOLUTION],
+            gain: 1.0,
+            texture: None,
+            read_position: 0,
+            previous: None,
+            left: Vec::with_capacity(GONIOMETER_RING_CAPACITY),
+            right: Vec::with_capacity(GONIOMETER_RING_CAPACITY),
+        }
+    }
+}
+
+impl GoniometerDisplay {
+    /// Fades the trails out, `persistence_ms` is how long it takes them to drop to about a third.
+    pub fn decay(&mut self, elapsed: f32, persistence_ms: f32) {
+        let decay = if persistence_ms > 0.0 {
+            (-1000.0 * elapsed / persistence_ms).exp()
+        } else {
+            0.0
+        };
+
+        for intensity in self.intensity.iter_mut() {
+            *intensity *= decay;
+        }
+    }
+
+    /// Accumulates the rotated samples [`goniometer_plugin_process`] wrote since the last frame.
+    /// Nothing is drawn when no new samples came in, so a stopped transport doesn't burn in.
+    pub fn draw(&mut self, ring: &SampleRing, rendering: GoniometerRendering, auto_gain: bool) {
+        let mut left = std::mem::take(&mut self.left);
+        let mut right = std::mem::take(&mut self.right);
+        left.clear();
+        right.clear();
+        self.read_position = ring.read_since(self.read_position, &mut left, &mut right);
+
+        if left.is_empty() {
+            self.previous = None;
+        }
+
+        // Jumps down right away so nothing gets clipped, but takes a while to zoom back in
+        self.gain = if auto_gain {
+            let peak = left
+                .iter()
+                .zip(right.iter())
+                .map(|(x, y)| x.abs().max(y.abs()))
+                .fold(0.0, f32::max);
+            let target = (0.9 / peak.max(1e-3)).clamp(1.0, 100.0);
+            if target < self.gain {
+                target
+            } else {
+                self.gain + (target - self.gain) * 0.05
+            }
+        } else {
+            1.0
+        };
+
+        for (x, y) in left.iter().zip(right.iter()) {
+            let point = self.to_pixel(x * self.gain, y * self.gain);
+            match (rendering, self.previous) {
+                (GoniometerRendering::Lines, Some(from)) => self.line(from, point),
+                _ => self.dot(point, 1.0),
+            }
+            self.previous = Some(point);
+        }
+
+        self.left = left;
+        self.right = right;
+    }
+
+    fn to_pixel(&self, x: f32, y: f32) -> Pos2 {
+        let scale = (GONIOMETER_RESOLUTION - 1) as f32 / 2.0;
+        Pos2::new(
+            (x.clamp(-1.0, 1.0) + 1.0) * scale,
+            (y.clamp(-1.0, 1.0) + 1.0) * scale,
+        )
+    }
+
+    fn dot(&mut self, point: Pos2, amount: f32) {
+        let idx = point.y.round() as usize * GONIOMETER_RESOLUTION + point.x.round() as usize;
+        self.intensity[idx] += amount;
+    }
+
+    /// The beam spends less time on every pixel the faster it moves, so a segment always adds
+    /// the same total intensity no matter how long it is.
+    fn line(&mut self, from: Pos2, to: Pos2) {
+        let steps = (to - from)
+            .abs()
+            .max_elem()
+            .ceil()
+            .clamp(1.0, GONIOMETER_RESOLUTION as f32);
+        for step in 1..=steps as usize {
+            self.dot(from.lerp(to, step as f32 / steps), 1.0 / steps);
+        }
+    }
+
+    pub fn texture(&mut self, ctx: &egui::Context, heatmap: bool) -> &egui::TextureHandle {
+        let pixels = self
+            .intensity
+            .iter()
+            .map(|intensity| {
+                // Saturates softly so dense areas don't all end up as the same flat blob
+                let level = 1.0 - (-0.25 * intensity).exp();
+                if heatmap {
+                    heatmap_color(level)
+                } else {
+                    Color32::LIGHT_BLUE.gamma_multiply(level)
+                }
+            })
+            .collect();
+        let image = egui::ColorImage {
+            size: [GONIOMETER_RESOLUTION, GONIOMETER_RESOLUTION],
+            pixels,
+        };
+
+        match &mut self.texture {
+            Some(texture) => texture.set(image, egui::TextureOptions::LINEAR),
+            None => {
+                self.texture =
+                    Some(ctx.load_texture("goniometer", image, egui::TextureOptions::LINEAR))
+            }
+        }
+
+        self.texture.as_ref().unwrap()
+    }
+}
+
+/// Maps a level between 0 and 1 to black, blue, cyan, yellow and finally white.
+pub fn heatmap_color(level: f32) -> Color32 {
+    gradient_color(
+        &[
+            [0.0, 0.0, 0.0],
+            [0.0, 0.2, 0.8],
+            [0.0, 0.8, 1.0],
+            [1.0, 0.9, 0.2],
+            [1.0, 1.0, 1.0],
+        ],
+        level,
+    )
+}
+
+/// Maps a level between 0 and 1 onto evenly spaced RGB color stops.
+pub fn gradient_color(stops: &[[f32; 3]], level: f32) -> Color32 {
+    let position = level.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+    let idx = (position as usize).min(stops.len() - 2);
+    let frac = position - idx as f32;
+    let [r, g, b] =
+        std::array::from_fn(|channel| lerp(stops[idx][channel], stops[idx + 1][channel], frac));
+
+    Color32::from_rgb((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8)
+}
+
+// Number of analyzer frames the spectrogram keeps on screen
+const SPECTROGRAM_HISTORY: usize = 256;
+
+/// Editor side state of the spectrogram, a ring of past spectra with the newest on the right.
+#[derive(Clone)]
+pub struct SpectrogramDisplay {
+    history: Vec<[f32; SPECTRUM_POINTS]>,
+    write_idx: usize,
+    texture: Option<egui::TextureHandle>,
+}
+
+impl Default for SpectrogramDisplay {
+    fn default() -> Self {
+        Self {
+            history: vec![[SPECTRUM_FLOOR_DB; SPECTRUM_POINTS]; SPECTROGRAM_HISTORY],
+            write_idx: 0,
+            texture: None,
+        }
+    }
+}
+
+impl SpectrogramDisplay {
+    /// Adds a column with the power average of the channels shown by the analyzer view.
+    pub fn push(&mut self, frame: &SpectrumFrame, channels: &[usize]) {
+        let column = &mut self.history[self.write_idx];
+        for (point, level) in column.iter_mut().enumerate() {
+            let power: f32 = channels
+                .iter()
+                .map(|&channel| db_to_gain(frame.levels[channel][point]).powi(2))
+                .sum::<f32>()
+                / channels.len() as f32;
+            *level = 10.0 * power.max(1e-20).log10();
+        }
+        self.write_idx = (self.write_idx + 1) % SPECTROGRAM_HISTORY;
+    }
+
+    pub fn texture(
+        &mut self,
+        ctx: &egui::Context,
+        color_map: SpectrogramColorMap,
+        range_db: f32,
+    ) -> &egui::TextureHandle {
+        let mut pixels = Vec::with_capacity(SPECTROGRAM_HISTORY * SPECTRUM_POINTS);
+        // Rows run from the highest frequency at the top down to the lowest
+        for point in (0..SPECTRUM_POINTS).rev() {
+            for age in 0..SPECTROGRAM_HISTORY {
+                let column = (self.write_idx + age) % SPECTROGRAM_HISTORY;
+                let level = 1.0 + self.history[column][point] / range_db;
+                pixels.push(color_map.color(level));
+            }
+        }
+        let image = egui::ColorImage {
+            size: [SPECTROGRAM_HISTORY, SPECTRUM_POINTS],
+            pixels,
+        };
+
+        match &mut self.texture {
+            Some(texture) => texture.set(image, egui::TextureOptions::LINEAR),
+            None => {
+                self.texture =
+                    Some(ctx.load_texture("spectrogram", image, egui::TextureOptions::LINEAR))
+            }
+        }
+
+        self.texture.as_ref().unwrap()
+    }
+}
+
+/// Scrolling time vs frequency heatmap of the analyzer output, 0 dBFS maps to the top of the
+/// color map and the dynamic range sets how far down the bottom goes.
+pub fn spectrogram_view(
+    ui: &mut egui::Ui,
+    view_settings: &mut ViewSettings,
+    spectrogram: &Mutex<triple_buffer::Output<SpectrumFrame>>,
+    display: &mut SpectrogramDisplay,
+) {
+    // Only whole new frames scroll the view, otherwise it would stall whenever the editor
+    // redraws faster than the analyzer produces frames
+    if let Ok(mut output) = spectrogram.try_lock()
+        && output.updated()
+    {
+        let frame = *output.read();
+        display.push(&frame, view_settings.analyzer_view.channels());
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Spectrogram");
+
+        for (option, label) in [
+            (SpectrogramColorMap::Heat, "Heat"),
+            (SpectrogramColorMap::Inferno, "Inferno"),
+            (SpectrogramColorMap::Grayscale, "Grayscale"),
+        ] {
+            ui.selectable_value(&mut view_settings.spectrogram_color_map, option, label);
+        }
+
+        ui.separator();
+
+        ui.label("Range");
+        ui.add(
+            egui::Slider::new(&mut view_settings.spectrogram_range, 30.0..=140.0)
+                .suffix(" dB")
+                .fixed_decimals(0),
+        );
+    });
+
+    let desired_size = vec2(ui.available_width(), ui.available_height().max(64.0));
+    let (response, painter) = ui.allocate_painter(desired_size, egui::Sense::hover());
+    let texture = display.texture(
+        ui.ctx(),
+        view_settings.spectrogram_color_map,
+        view_settings.spectrogram_range,
+    );
+    painter.image(
+        texture.id(),
+        response.rect,
+        Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+        Color32::WHITE,
+    );
+}
+
+// Vertical range of the oscilloscope, a bit beyond full scale so clipping stays visible
+const OSCILLOSCOPE_RANGE: f32 = 1.25;
+
+/// Editor side state of the oscilloscope, the most recent input and output samples.
+#[derive(Clone)]
+pub struct OscilloscopeDisplay {
+    read_position: usize,
+    input: Vec<f32>,
+    output: Vec<f32>,
+}
+
+impl Default for OscilloscopeDisplay {
+    fn default() -> Self {
+        Self {
+            read_position: 0,
+            input: Vec::with_capacity(OSCILLOSCOPE_RING_CAPACITY * 2),
+            output: Vec::with_capacity(OSCILLOSCOPE_RING_CAPACITY * 2),
+        }
+    }
+}
+
+impl OscilloscopeDisplay {
+    fn update(&mut self, oscilloscope: &Oscilloscope) {
+        self.read_position =
+            oscilloscope
+                .samples
+                .read_since(self.read_position, &mut self.input, &mut self.output);
+
+        let excess = self.input.len().saturating_sub(OSCILLOSCOPE_RING_CAPACITY);
+        self.input.drain(..excess);
+        self.output.drain(..excess);
+    }
+
+    /// Start of the window to draw. With the trigger enabled this is the most recent rising edge
+    /// through `level` that still leaves room for a whole window, otherwise the view free runs.
+    fn window_start(&self, window: usize, latency: usize, trigger: Option<f32>) -> usize {
+        let last_start = self.input.len().saturating_sub(window + latency);
+
+        trigger
+            .and_then(|level| {
+                (1..=last_start)
+                    .rev()
+                    .find(|&idx| self.input[idx - 1] < level && self.input[idx] >= level)
+            })
+            .unwrap_or(last_start)
+    }
+}
+
+/// Triggered waveform view of the selected effect's output, optionally with its input overlaid.
+pub fn oscilloscope_view(
+    ui: &mut egui::Ui,
+    view_settings: &mut ViewSettings,
+    oscilloscope: &Oscilloscope,
+    display: &mut OscilloscopeDisplay,
+) {
+    let axis_color = Color32::from_additive_luminance(64);
+    let input_color = Color32::LIGHT_BLUE.gamma_multiply(0.6);
+    let output_color = Color32::LIGHT_GREEN;
+
+    display.update(oscilloscope);
+
+    ui.horizontal(|ui| {
+        ui.label("Oscilloscope");
+
+        ui.toggle_value(&mut view_settings.oscilloscope_trigger, "Trigger");
+        ui.toggle_value(&mut view_settings.oscilloscope_show_input, "Show Input");
+
+        ui.separator();
+
+        ui.label("Window");
+        ui.add(
+            egui::Slider::new(&mut view_settings.oscilloscope_window, 1.0..=100.0)
+                .logarithmic(true)
+                .suffix(" ms")
+                .fixed_decimals(1),
+        );
+
+        ui.label("Level");
+        ui.add(
+            egui::Slider::new(&mut view_settings.oscilloscope_trigger_level, -1.0..=1.0)
+                .fixed_decimals(2),
+        );
+    });
+
+    let desired_size = vec2(ui.available_width(), ui.available_height().max(64.0));
+    let (response, painter) = ui.allocate_painter(desired_size, egui::Sense::hover());
+    let rect = response.rect;
+
+    let window = ((view_settings.oscilloscope_window / 1000.0 * oscilloscope.sample_rate())
+        as usize)
+        .clamp(2, OSCILLOSCOPE_RING_CAPACITY / 2);
+    let to_screen = emath::RectTransform::from_to(
+        Rect::from_x_y_ranges(
+            0.0..=(window - 1) as f32,
+            OSCILLOSCOPE_RANGE..=-OSCILLOSCOPE_RANGE,
+        ),
+        rect,
+    );
+
+    for level in [-1.0, -0.5, 0.0, 0.5, 1.0] {
+        let y = (to_screen * Pos2::new(0.0, level)).y;
+        painter.line_segment(
+            [Pos2::new(rect.left(), y), Pos2::new(rect.right(), y)],
+            Stroke::new(1.0, axis_color),
+        );
+    }
+
+    let trigger_level = view_settings.oscilloscope_trigger_level;
+    let trigger = view_settings.oscilloscope_trigger.then_some(trigger_level);
+    if trigger.is_some() {
+        let y = (to_screen * Pos2::new(0.0, trigger_level)).y;
+        painter.line_segment(
+            [Pos2::new(rect.left(), y), Pos2::new(rect.left() + 8.0, y)],
+            Stroke::new(2.0, Color32::LIGHT_YELLOW),
+        );
+    }
+
+    // The trigger looks at the input, and the output is drawn from where that input comes out of
+    // the effect, so the two traces stay lined up through any oversampling latency
+    let latency = oscilloscope.latency();
+    if display.input.len() < window + latency {
+        return;
+    }
+    let start = display.window_start(window, latency, trigger);
+
+    let trace = |samples: &[f32]| {
+        samples
+            .iter()
+            .enumerate()
+            .map(|(idx, sample)| {
+                to_screen
+                    * Pos2::new(
+                        idx as f32,
+                        sample.clamp(-OSCILLOSCOPE_RANGE, OSCILLOSCOPE_RANGE),
+                    )
+            })
+            .collect::<Vec<Pos2>>()
+    };
+
+    if view_settings.oscilloscope_show_input {
+        painter.add(egui::epaint::Shape::line(
+            trace(&display.input[start..start + window]),
+            Stroke::new(1.0, input_color),
+        ));
+    }
+    painter.add(egui::epaint::Shape::line(
+        trace(&display.output[start + latency..start + latency + window]),
+        Stroke::new(1.5, output_color),
+    ));
+}
+
+/// Editor side state of the spectrum analyzer.
+#[derive(Clone)]
+pub struct SpectrumDisplay {
+    levels: [[f32; SPECTRUM_POINTS]; 4],
+    peaks: [[f32; SPECTRUM_POINTS]; 4],
+}
+
+impl Default for SpectrumDisplay {
+    fn default() -> Self {
+        Self {
+            levels: [[SPECTRUM_FLOOR_DB; SPECTRUM_POINTS]; 4],
+            peaks: [[SPECTRUM_FLOOR_DB; SPECTRUM_POINTS]; 4],
+        }
+    }
+}
+
+// How fast held peaks fall back down
+const SPECTRUM_PEAK_FALL_DB_PER_SECOND: f32 = 12.0;
+// Range the spectrum plot covers
+const SPECTRUM_TOP_DB: f32 = 6.0;
+const SPECTRUM_BOTTOM_DB: f32 = -96.0;
+
+/// Log frequency spectrum of the left, right, mid or side signals with optional peak hold.
+pub fn spectrum_view(
+    ui: &mut egui::Ui,
+    view_settings: &mut ViewSettings,
+    spectrum: &Mutex<triple_buffer::Output<SpectrumFrame>>,
+    display: &mut SpectrumDisplay,
+) {
+    let axis_color = Color32::from_additive_luminance(64);
+    let channel_colors = [
+        Color32::LIGHT_BLUE,
+        Color32::LIGHT_RED,
+        Color32::LIGHT_GREEN,
+        Color32::LIGHT_YELLOW,
+    ];
+
+    if let Ok(mut output) = spectrum.try_lock() {
+        display.levels = output.read().levels;
+    }
+
+    let peak_hold = view_settings.analyzer_peak_hold;
+    let fall = SPECTRUM_PEAK_FALL_DB_PER_SECOND * ui.input(|input| input.stable_dt);
+    for (peaks, levels) in display.peaks.iter_mut().zip(display.levels.iter()) {
+        for (peak, level) in peaks.iter_mut().zip(levels.iter()) {
+            *peak = if peak_hold {
+                (*peak - fall).max(*level)
+            } else {
+                *level
+            };
+        }
+    }
+
+    // Tilts the display around 1 kHz, so pink noise or a typical mix looks roughly flat
+    let slope = view_settings.analyzer_slope;
+    let slope_offset =
+        |point: usize| slope * (spectrum_point_frequency(point as f32) / 1000.0).log2();
+
+    let desired_size = vec2(ui.available_width(), ui.available_width() * 0.5);
+    let (response, painter) = ui.allocate_painter(desired_size, egui::Sense::hover());
+    let rect = response.rect;
+    let to_screen = emath::RectTransform::from_to(
+        Rect::from_x_y_ranges(
+            0.0..=(SPECTRUM_POINTS - 1) as f32,
+            SPECTRUM_TOP_DB..=SPECTRUM_BOTTOM_DB,
+        ),
+        rect,
+    );
+
+    for frequency in [
+        50.0, 100.0, 200.0, 500.0, 1_000.0, 2_000.0, 5_000.0, 10_000.0,
+    ] {
+        let point = (frequency / SPECTRUM_MIN_HZ).ln() / (SPECTRUM_MAX_HZ / SPECTRUM_MIN_HZ).ln()
+            * (SPECTRUM_POINTS - 1) as f32;
+        let x = (to_screen * Pos2::new(point, 0.0)).x;
+        painter.line_segment(
+            [Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())],
+            Stroke::new(1.0, axis_color),
+        );
+        let label = if frequency >= 1_000.0 {
+            format!("{}k", frequency / 1_000.0)
+        } else {
+            format!("{frequency}")
+        };
+        painter.text(
+            Pos2::new(x + 2.0, rect.bottom() - 2.0),
+            egui::Align2::LEFT_BOTTOM,
+            label,
+            egui::FontId::proportional(10.0),
+            axis_color,
+        );
+    }
+    for db in (SPECTRUM_BOTTOM_DB as i32..=0).rev().step_by(12) {
+        let y = (to_screen * Pos2::new(0.0, db as f32)).y;
+        painter.line_segment(
+            [Pos2::new(rect.left(), y), Pos2::new(rect.right(), y)],
+            Stroke::new(1.0, axis_color),
+        );
+    }
+
+    let line = |levels: &[f32; SPECTRUM_POINTS]| {
+        levels
+            .iter()
+            .enumerate()
+            .filter(|(_, level)| **level > SPECTRUM_FLOOR_DB)
+            .map(|(point, level)| {
+                let level =
+                    (level + slope_offset(point)).clamp(SPECTRUM_BOTTOM_DB, SPECTRUM_TOP_DB);
+                to_screen * Pos2::new(point as f32, level)
+            })
+            .collect::<Vec<Pos2>>()
+    };
+
+    for &channel in view_settings.analyzer_view.channels() {
+        let color = channel_colors[channel];
+        if peak_hold {
+            painter.add(egui::epaint::Shape::line(
+                line(&display.peaks[channel]),
+                Stroke::new(1.0, color.gamma_multiply(0.5)),
+            ));
+        }
+        painter.add(egui::epaint::Shape::line(
+            line(&display.levels[channel]),
+            Stroke::new(1.5, color),
+        ));
+    }
+
+    ui.horizontal(|ui| {
+        for (option, label) in [
+            (SpectrumView::LeftRight, "L/R"),
+            (SpectrumView::MidSide, "M/S"),
+            (SpectrumView::Left, "L"),
+            (SpectrumView::Right, "R"),
+            (SpectrumView::Mid, "M"),
+            (SpectrumView::Side, "S"),
+        ] {
+            ui.selectable_value(&mut view_settings.analyzer_view, option, label);
+        }
+
+        ui.separator();
+
+        ui.toggle_value(&mut view_settings.analyzer_peak_hold, "Peak Hold");
+    });
+
+    ui.label("FFT Size");
+    ui.horizontal(|ui| {
+        for (option, label) in [
+            (FftSize::S1024, "1024"),
+            (FftSize::S2048, "2048"),
+            (FftSize::S4096, "4096"),
+            (FftSize::S8192, "8192"),
+            (FftSize::S16384, "16384"),
+        ] {
+            ui.selectable_value(&mut view_settings.analyzer_fft_size, option, label);
+        }
+    });
+
+    ui.label("Window");
+    ui.horizontal(|ui| {
+        for (option, label) in [
+            (SpectrumWindow::Rectangular, "Rectangular"),
+            (SpectrumWindow::Hann, "Hann"),
+            (SpectrumWindow::BlackmanHarris, "Blackman-Harris"),
+            (SpectrumWindow::FlatTop, "Flat Top"),
+        ] {
+            ui.selectable_value(&mut view_settings.analyzer_window, option, label);
+        }
+    });
+
+    ui.label("Averaging");
+    ui.add(
+        egui::Slider::new(&mut view_settings.analyzer_averaging, 0.0..=5_000.0)
+            .logarithmic(true)
+            .suffix(" ms")
+            .fixed_decimals(0),
+    );
+
+    ui.label("Slope (dB/oct)");
+    ui.add(egui::Slider::new(&mut view_settings.analyzer_slope, 0.0..=6.0).fixed_decimals(1));
+}
+
+// Level meter scale and ballistics, the bars fall back slower than they rise so peaks stay readable
+const LEVEL_METER_FLOOR_DB: f32 = -60.0;
+const LEVEL_METER_FALL_DB_PER_SECOND: f32 = 20.0;
+const LEVEL_METER_HOLD_SECONDS: f32 = 1.5;
+
+/// Editor side state of one level meter.
+#[derive(Clone)]
+pub struct LevelDisplay {
+    peaks: [f32; 2],
+    rms: [f32; 2],
+    holds: [f32; 2],
+    hold_ages: [f32; 2],
+    clipped: [bool; 2],
+}
+
+impl Default for LevelDisplay {
+    fn default() -> Self {
+        Self {
+            peaks: [LEVEL_METER_FLOOR_DB; 2],
+            rms: [LEVEL_METER_FLOOR_DB; 2],
+            holds: [LEVEL_METER_FLOOR_DB; 2],
+            hold_ages: [0.0; 2],
+            clipped: [false; 2],
+        }
+    }
+}
+
+impl LevelDisplay {
+    fn update(&mut self, meter: &LevelMeter, elapsed: f32) {
+        for channel in 0..2 {
+            let (peak, rms) = meter.take(channel);
+            let peak_db = util::gain_to_db(peak).max(LEVEL_METER_FLOOR_DB);
+
+            self.peaks[channel] =
+                (self.peaks[channel] - LEVEL_METER_FALL_DB_PER_SECOND * elapsed).max(peak_db);
+            self.rms[channel] = util::gain_to_db(rms).max(LEVEL_METER_FLOOR_DB);
+
+            self.hold_ages[channel] += elapsed;
+            if peak_db >= self.holds[channel] {
+                self.holds[channel] = peak_db;
+                self.hold_ages[channel] = 0.0;
+            } else if self.hold_ages[channel] > LEVEL_METER_HOLD_SECONDS {
+                self.holds[channel] = self.peaks[channel];
+            }
+
+            self.clipped[channel] |= peak >= 1.0;
+        }
+    }
+
+    fn reset_latches(&mut self) {
+        self.holds = self.peaks;
+        self.hold_ages = [0.0; 2];
+        self.clipped = [false; 2];
+    }
+}
+
+/// Compact stereo peak and RMS meter for the menu bar. The red boxes latch once a channel hits
+/// 0 dBFS, clicking the meter clears them along with the peak hold.
+pub fn level_meter(ui: &mut egui::Ui, label: &str, meter: &LevelMeter, display: &mut LevelDisplay) {
+    display.update(meter, ui.input(|input| input.stable_dt));
+
+    ui.label(label);
+
+    let (response, painter) = ui.allocate_painter(vec2(120.0, 14.0), egui::Sense::click());
+    let rect = response.rect;
+    let clip_width = 8.0;
+    let bar_right = rect.right() - clip_width - 2.0;
+    let to_x = |db: f32| {
+        rect.left() + (1.0 - db / LEVEL_METER_FLOOR_DB).clamp(0.0, 1.0) * (bar_right - rect.left())
+    };
+
+    for channel in 0..2 {
+        let bar_height = (rect.height() - 2.0) / 2.0;
+        let top = rect.top() + channel as f32 * (bar_height + 2.0);
+        let y_range = top..=top + bar_height;
+
+        painter.rect_filled(
+            Rect::from_x_y_ranges(rect.left()..=bar_right, y_range.clone()),
+            0.0,
+            Color32::from_additive_luminance(32),
+        );
+        painter.rect_filled(
+            Rect::from_x_y_ranges(rect.left()..=to_x(display.peaks[channel]), y_range.clone()),
+            0.0,
+            Color32::from_rgb(40, 120, 50),
+        );
+        painter.rect_filled(
+            Rect::from_x_y_ranges(rect.left()..=to_x(display.rms[channel]), y_range.clone()),
+            0.0,
+            Color32::from_rgb(80, 210, 100),
+        );
+
+        let hold_x = to_x(display.holds[channel]);
+        painter.line_segment(
+            [Pos2::new(hold_x, top), Pos2::new(hold_x, top + bar_height)],
+            Stroke::new(1.5, Color32::LIGHT_YELLOW),
+        );
+
+        let clip_color = if display.clipped[channel] {
+            Color32::RED
+        } else {
+            Color32::from_additive_luminance(48)
+        };
+        painter.rect_filled(
+            Rect::from_x_y_ranges(rect.right() - clip_width..=rect.right(), y_range),
+            0.0,
+            clip_color,
+        );
+    }
+
+    if response.on_hover_text("Click to reset").clicked() {
+        display.reset_latches();
+    }
+
+    let hold = display.holds[0].max(display.holds[1]);
+    if hold > LEVEL_METER_FLOOR_DB {
+        ui.monospace(format!("{hold:>5.1}"));
+    } else {
+        ui.monospace(" -inf");
+    }
+}
+
+/// Toggle and per path controls for running the selected effect in mid/side.
+pub fn mid_side_wrapper_controls(ui: &mut egui::Ui, params: &PluginParams, setter: &ParamSetter) {
+    ui.horizontal(|ui| {
+        let mid_side = params.mid_side_processing.value();
+        if ui
+            .add(egui::widgets::SelectableLabel::new(
+                mid_side,
+                "M/S Processing",
+            ))
+            .clicked()
+        {
+            setter.begin_set_parameter(&params.mid_side_processing);
+            setter.set_parameter(&params.mid_side_processing, !mid_side);
+            setter.end_set_parameter(&params.mid_side_processing);
+        }
+    });
+
+    if params.mid_side_processing.value() {
+        let [mid, side] = &params.mid_side_paths;
+
+        egui::Grid::new("mid-side-paths").show(ui, |ui| {
+            ui.label("");
+            ui.label("Mid");
+            ui.label("Side");
+            ui.end_row();
+
+            mid_side_path_row(
+                ui,
+                setter,
+                "Mix",
+                &params.mid_side_mid_mix,
+                &params.mid_side_side_mix,
+            );
+            mid_side_path_row(
+                ui,
+                setter,
+                "Gain",
+                &params.mid_side_mid_gain,
+                &params.mid_side_side_gain,
+            );
+
+            match params.selected_fx.value() {
+                Fx::Distortion => {
+                    mid_side_path_row(
+                        ui,
+                        setter,
+                        "Amount",
+                        &mid.distortion_amount,
+                        &side.distortion_amount,
+                    );
+                    mid_side_path_row(
+                        ui,
+                        setter,
+                        "Drive",
+                        &mid.distortion_drive,
+                        &side.distortion_drive,
+                    );
+                    mid_side_path_row(
+                        ui,
+                        setter,
+                        "Distortion Mix",
+                        &mid.distortion_mix,
+                        &side.distortion_mix,
+                    );
+                }
+                Fx::Delay => {
+                    mid_side_path_row(ui, setter, "Time", &mid.delay_time, &side.delay_time);
+                    mid_side_path_row(
+                        ui,
+                        setter,
+                        "Feedback",
+                        &mid.delay_feedback,
+                        &side.delay_feedback,
+                    );
+                    mid_side_path_row(
+                        ui,
+                        setter,
+                        "Wet Out",
+                        &mid.delay_wet_out,
+                        &side.delay_wet_out,
+                    );
+                }
+                Fx::Chorus => {
+                    mid_side_path_row(ui, setter, "Time", &mid.chorus_time, &side.chorus_time);
+                    mid_side_path_row(ui, setter, "Depth", &mid.chorus_depth, &side.chorus_depth);
+                    mid_side_path_row(
+                        ui,
+                        setter,
+                        "Wet Out",
+                        &mid.chorus_out_mix_wet,
+                        &side.chorus_out_mix_wet,
+                    );
+                }
+                _ => (),
+            }
+        });
+    }
+}
+
+fn mid_side_path_row<P: Param>(
+    ui: &mut egui::Ui,
+    setter: &ParamSetter,
+    label: &str,
+    mid: &P,
+    side: &P,
+) {
+    ui.label(label);
+    ui.add(widgets::ParamSlider::for_param(mid, setter));
+    ui.add(widgets::ParamSlider::for_param(side, setter));
+    ui.end_row();
+}
+
+/// Loudness readouts in a single row, with a button to start a fresh measurement.
+pub fn loudness_view(
+    ui: &mut egui::Ui,
+    params: &PluginParams,
+    loudness: &Mutex<triple_buffer::Output<LoudnessReadout>>,
+) {
+    let readout = match loudness.try_lock() {
+        Ok(mut output) => *output.read(),
+        Err(_) => return,
+    };
+
+    let format = |value: f32, unit: &str| {
+        if value.is_finite() && value > -100.0 {
+            format!("{value:.1} {unit}")
+        } else {
+            format!("-inf {unit}")
+        }
+    };
+
+    ui.horizontal(|ui| {
+        for (label, value) in [
+            ("M", format(readout.momentary, "LUFS")),
+            ("S", format(readout.short_term, "LUFS")),
+            ("I", format(readout.integrated, "LUFS")),
+            ("LRA", format!("{:.1} LU", readout.range)),
+            ("TP", format(readout.true_peak, "dBTP")),
+        ] {
+            ui.label(label);
+            ui.monospace(value);
+            ui.separator();
+        }
+
+        if ui.button("Reset").clicked() {
+            params.loudness_reset.store(true, Ordering::Relaxed);
+        }
+    });
+}
+
+/// Horizontal bar for a value between -1 and +1 that grows outwards from the center.
+pub fn stereo_meter_bar(ui: &mut egui::Ui, value: f32, color: Color32) {
+    let desired_size = vec2(ui.available_width() * 0.8, 12.0);
+    let (response, painter) = ui.allocate_painter(desired_size, egui::Sense::hover());
+    let rect = response.rect;
+
+    painter.rect_filled(rect, 0.0, Color32::from_additive_luminance(32));
+    let value_x = rect.center().x + value.clamp(-1.0, 1.0) * rect.width() / 2.0;
+    let bar = Rect::from_x_y_ranges(
+        rect.center().x.min(value_x)..=rect.center().x.max(value_x),
+        rect.y_range(),
+    );
+    painter.rect_filled(bar, 0.0, color);
+    painter.line_segment(
+        [rect.center_top(), rect.center_bottom()],
+        Stroke::new(1.0, Color32::from_additive_luminance(196)),
+    );
+}
+
 /// Linear panning from Hack Audio Book
-pub fn panning_plugin_process(buffer: &mut Buffer, params: &Arc<PluginParams>) -> ProcessStatus {
+pub fn panning_plugin_process(
+    sample_rate: f32,
+    buffer: &mut Buffer,
+    params: &Arc<PluginParams>,
+    binaural: &mut Binaural,
+) -> ProcessStatus {
     let pan_value = params.pan.value();
     let panning_mode = params.panning_mode.value();
     let pan_transform = (pan_value / 200.0) + 0.5;
 
-    for channel_samples in buffer.iter_samples() {
-        for (channel, sample) in channel_samples.into_iter().enumerate() {
-            if channel == 0 {
-                // Assumes only left and right channels
-                let new_sample = match panning_mode {
-                    PanningMode::Linear => 1.0 - pan_transform,
-                    PanningMode::Square => (1.0 - pan_transform).sqrt(),
-                    PanningMode::Sine => {
-                        ((1.0 - pan_transform) * (std::f32::consts::PI / 2.0)).sin()
+    match params.panning_source.value() {
+        PanningSource::Mono => {
+            let left_gain = panning_mode.pan_gain(1.0 - pan_transform);
+            let right_gain = panning_mode.pan_gain(pan_transform);
+
+            for channel_samples in buffer.iter_samples() {
+                for (channel, sample) in channel_samples.into_iter().enumerate() {
+                    // Assumes only left and right channels
+                    if channel == 0 {
+                        *sample *= left_gain;
+                    } else {
+                        *sample *= right_gain;
                     }
-                };
+                }
+            }
+        }
+        PanningSource::Balance => {
+            // Normalized to the center so balance never boosts, the law only shapes how the far
+            // channel fades out
+            let center_gain = panning_mode.pan_gain(0.5);
+            let left_gain = (panning_mode.pan_gain(1.0 - pan_transform) / center_gain).min(1.0);
+            let right_gain = (panning_mode.pan_gain(pan_transform) / center_gain).min(1.0);
+
+            for channel_samples in buffer.iter_samples() {
+                for (channel, sample) in channel_samples.into_iter().enumerate() {
+                    if channel == 0 {
+                        *sample *= left_gain;
+                    } else {
+                        *sample *= right_gain;
+                    }
+                }
+            }
+        }
+        PanningSource::Stereo => {
+            // The input channels become two mono sources spread `width` apart around the pan
+            // position, at full width and centered that's the untouched stereo image
+            let width = params.stereo_pan_width.value() / 100.0;
+            let left_position = pan_transform - width / 2.0;
+            let right_position = pan_transform + width / 2.0;
+            let left_to_left = panning_mode.pan_gain(1.0 - left_position);
+            let left_to_right = panning_mode.pan_gain(left_position);
+            let right_to_left = panning_mode.pan_gain(1.0 - right_position);
+            let right_to_right = panning_mode.pan_gain(right_position);
+
+            let num_samples = buffer.samples();
+            let output = buffer.as_slice();
+
+            // A mono signal has no image to move
+            if output.len() < 2 {
+                return ProcessStatus::Normal;
+            }
+
+            let (left_channel, right_channel) = output.split_at_mut(1);
+            for (left, right) in left_channel[0]
+                .iter_mut()
+                .zip(right_channel[0].iter_mut())
+                .take(num_samples)
+            {
+                (*left, *right) = (
+                    *left * left_to_left + *right * right_to_left,
+                    *left * left_to_right + *right * right_to_right,
+                );
+            }
+        }
+        PanningSource::Binaural => {
+            let num_samples = buffer.samples();
+            let output = buffer.as_slice();
 
-                *sample *= new_sample;
-            } else {
-                let new_sample = match panning_mode {
-                    PanningMode::Linear => pan_transform,
-                    PanningMode::Square => (pan_transform).sqrt(),
-                    PanningMode::Sine => (pan_transform * (std::f32::consts::PI / 2.0)).sin(),
-                };
+            // Headphones need two channels
+            if output.len() < 2 {
+                return ProcessStatus::Normal;
+            }
 
-                *sample *= new_sample;
+            // While the position moves it's updated every sample, the delays and the head shadow
+            // filters would otherwise jump once per block and click
+            let azimuth = &params.binaural_azimuth.smoothed;
+            let elevation = &params.binaural_elevation.smoothed;
+            let (left_channel, right_channel) = output.split_at_mut(1);
+            for (sample_idx, (left, right)) in left_channel[0]
+                .iter_mut()
+                .zip(right_channel[0].iter_mut())
+                .take(num_samples)
+                .enumerate()
+            {
+                if sample_idx == 0 || azimuth.is_smoothing() || elevation.is_smoothing() {
+                    binaural.set_position(sample_rate, azimuth.next(), elevation.next());
+                }
+                (*left, *right) = binaural.process((*left + *right) * 0.5);
             }
         }
     }
@@ -1018,67 +6419,64 @@ pub fn chorus_plugin_process(
     sample_rate: f32,
     buffer: &mut Buffer, 
     params: &Arc<PluginParams>,
-    chorus: &mut Chorus,
+    mid_side: bool,
+    chorus: &mut [Chorus; 2],
 ) -> ProcessStatus {
-    let chorus_time_ms = params.chorus_time.value();
     // TODO - Store voice count on chorus so the lfos only get initialized on change.
     let voice_count = params.chorus_voice_count.value();
     let rate_hz = params.chorus_rate.value();
     let rate_radians = rate_hz * 2.0 * std::f32::consts::PI / sample_rate;
-    let depth = params.chorus_depth.value();
     let dry_out = db_to_gain(params.chorus_out_mix_dry.value() as f32);
-    let wet_out = db_to_gain(params.chorus_out_mix_wet.value() as f32);
-    let wet_mix = wet_out / voice_count as f32;
 
-    let buffer_capacity = chorus.buffer.len();
     let num_samples = buffer.samples();
     let output = buffer.as_slice();
 
-    let sample_length = chorus_time_ms * sample_rate * 0.001; // (ms * samples/sec * 0.001)
-    let spread = sample_length / voice_count as f32 * depth;
-
-    for sample_idx in 0..num_samples {
-        // Save original sample to write into the chorus buffer
-        let left_in = output[0][sample_idx];
-        let mut out = left_in * dry_out;
-        // let _dry_r = output[1][sample_idx] * dry_out;
-
-        // Take care of circular buffer
-        // let chorus_idx = (chorus.buffer_idx + sample_idx as usize) % sample_length as usize;
-        let chorus_idx = chorus.buffer_idx % sample_length as usize;
-
-        /*
-            Main algorithm
-            - For each LFO, calculate the LFO oscillator position, which will most likely not be an integer value
-                - LERP the output value to be the current chorus buffer position with the next one.
-                - Add the LERPed value to the dry mixed value and set that as the output sample
-        */
-        for voice_idx in 0..voice_count as usize {
-            // TODO - Experiment with different LFO shapes and numerically stable oscillator(phasor)
-            chorus.lfos[voice_idx] += rate_radians; // Always incrementing instead of being between bound 0..2pi... :(
-            let mut chorus_pos: f32 = chorus_idx as f32 - (0.5 + 0.49 * chorus.lfos[voice_idx].sin()) * (voice_idx + 1) as f32 * spread;
-
-            if chorus_pos < 0.0 {
-                chorus_pos += sample_length;
-            }
-
-            let mut next_chorus_pos = chorus_pos + 1.0;
-
-            if chorus_pos >= sample_length - 1.0 {
-                next_chorus_pos = 0.0;
+    let settings = |chorus_time_ms: f32, depth: f32, wet_out_db: f32| {
+        let sample_length = chorus_time_ms * sample_rate * 0.001; // (ms * samples/sec * 0.001)
+        ChorusSettings {
+            voice_count: voice_count as usize,
+            rate_radians,
+            sample_length,
+            spread: sample_length / voice_count as f32 * depth,
+            dry_out,
+            wet_mix: db_to_gain(wet_out_db) / voice_count as f32,
+        }
+    };
+
+    if mid_side {
+        // Mid and side each go through their own chorus with their own time, depth and level
+        for ((channel, chorus), path) in output
+            .iter_mut()
+            .zip(chorus.iter_mut())
+            .zip(params.mid_side_paths.iter())
+        {
+            let settings = settings(
+                path.chorus_time.value(),
+                path.chorus_depth.value(),
+                path.chorus_out_mix_wet.value(),
+            );
+            for sample in channel.iter_mut().take(num_samples) {
+                *sample = chorus.process(*sample, &settings);
             }
-
-            // Out will accumulate the interpolated values from the chorus delay buffer which are calculated by positions of the lfo voice
-            let fractional = chorus_pos.fract();
-            out += wet_mix * (chorus.buffer[chorus_pos as usize] * (1.0 - fractional) + chorus.buffer[next_chorus_pos as usize] * fractional);
         }
-        
-        chorus.buffer[chorus_idx] = left_in;
-        chorus.buffer_idx = (chorus.buffer_idx + 1) % sample_length as usize;
+    } else {
+        let settings = settings(
+            params.chorus_time.value(),
+            params.chorus_depth.value(),
+            params.chorus_out_mix_wet.value(),
+        );
 
         // Mono output
-        output[0][sample_idx] = out;
-        output[1][sample_idx] = out;
+        let (left, right) = output.split_at_mut(1);
+        for (left, right) in left[0]
+            .iter_mut()
+            .zip(right[0].iter_mut())
+            .take(num_samples)
+        {
+            let out = chorus[0].process(*left, &settings);
+            *left = out;
+            *right = out;
+        }
     }
 
     ProcessStatus::Normal
@@ -1088,34 +6486,44 @@ pub fn delay_plugin_process(
     sample_rate: f32,
     buffer: &mut Buffer, 
     params: &Arc<PluginParams>,
+    mid_side: bool,
     delay: &mut DelayBuffer,
 ) -> ProcessStatus {
-    let feedback = db_to_gain(params.delay_feedback.value() as f32);
     let mix_in = db_to_gain(params.delay_mix_in.value() as f32);
-    let wet_out = db_to_gain(params.delay_wet_out.value() as f32);
     let dry_out = db_to_gain(params.delay_dry_out.value() as f32);
 
-    let delay_time_ms = params.delay_time.value();
-    let delay_samples = (sample_rate * (delay_time_ms as f32 / 1000.0)) as usize;
+    // Left and right share the settings, mid and side each have their own
+    let mut feedback = [db_to_gain(params.delay_feedback.value() as f32); 2];
+    let mut wet_out = [db_to_gain(params.delay_wet_out.value() as f32); 2];
+    let mut delay_time_ms = [params.delay_time.value(); 2];
+    if mid_side {
+        for (path_idx, path) in params.mid_side_paths.iter().enumerate() {
+            feedback[path_idx] = db_to_gain(path.delay_feedback.value() as f32);
+            wet_out[path_idx] = db_to_gain(path.delay_wet_out.value() as f32);
+            delay_time_ms[path_idx] = path.delay_time.value();
+        }
+    }
+
+    let delay_samples = delay_time_ms.map(|delay_time_ms| (sample_rate * (delay_time_ms as f32 / 1000.0)) as usize);
     let buffer_len = delay.left_buffer.len();
     let num_samples = buffer.samples();
     let output = buffer.as_slice();
 
     for sample_idx in 0..num_samples {
         let write_idx = (delay.current_index + sample_idx) % buffer_len;
-        let read_idx = (write_idx + buffer_len - delay_samples) % buffer_len;
+        let read_idx = delay_samples.map(|delay_samples| (write_idx + buffer_len - delay_samples) % buffer_len);
 
         let dry_l = output[0][sample_idx];
         let dry_r = output[1][sample_idx];
 
-        let delay_l = delay.left_buffer[read_idx];
-        let delay_r = delay.right_buffer[read_idx];
+        let delay_l = delay.left_buffer[read_idx[0]];
+        let delay_r = delay.right_buffer[read_idx[1]];
 
-        delay.left_buffer[write_idx] = (((dry_l * mix_in) + (delay_l * feedback)).max(-4.0)).min(4.0);
-        delay.right_buffer[write_idx] = (((dry_r * mix_in) + (delay_r * feedback)).max(-4.0)).min(4.0);
+        delay.left_buffer[write_idx] = (((dry_l * mix_in) + (delay_l * feedback[0])).max(-4.0)).min(4.0);
+        delay.right_buffer[write_idx] = (((dry_r * mix_in) + (delay_r * feedback[1])).max(-4.0)).min(4.0);
 
-        output[0][sample_idx] = dry_l * dry_out + delay_l * wet_out; 
-        output[1][sample_idx] = dry_r * dry_out + delay_r * wet_out; 
+        output[0][sample_idx] = dry_l * dry_out + delay_l * wet_out[0]; 
+        output[1][sample_idx] = dry_r * dry_out + delay_r * wet_out[1]; 
     }
 
     delay.current_index = (delay.current_index + num_samples) % buffer_len;
@@ -1123,104 +6531,690 @@ pub fn delay_plugin_process(
     ProcessStatus::Normal
 }
 
-pub fn lerp(start: f32, end: f32, amount: f32) -> f32 {
-    start * (1.0 - amount) + end * amount
+// Closest the custom curve's points can get to each other horizontally
+const CUSTOM_CURVE_MIN_SPACING: f32 = 0.02;
+
+/// Editor for the [`DistortionMode::Custom`] curve. Points can be dragged around, double clicking
+/// adds a point and right clicking removes one. The end points can only be moved vertically.
+pub fn custom_curve_editor(ui: &mut egui::Ui, params: &PluginParams) {
+    let axis_color = Color32::from_additive_luminance(64);
+    let point_radius = 4.0;
+
+    let mut points = params.custom_curve.read().clone();
+    let mut changed = sanitize_custom_curve(&mut points);
+
+    if ui.button("Reset Curve").clicked() {
+        points = default_custom_curve();
+        changed = true;
+    }
+
+    let desired_size = ui.available_width().min(300.0) * vec2(1.0, 1.0);
+    let (response, painter) = ui.allocate_painter(desired_size, egui::Sense::click());
+    let to_screen =
+        emath::RectTransform::from_to(Rect::from_x_y_ranges(-1.0..=1.0, 1.0..=-1.0), response.rect);
+    let from_screen = to_screen.inverse();
+
+    let last = points.len() - 1;
+    let mut removed = None;
+    for idx in 0..points.len() {
+        let (x, y) = points[idx];
+        let center = to_screen * Pos2::new(x, y);
+        let point_response = ui.interact(
+            Rect::from_center_size(center, Vec2::splat(point_radius * 3.0)),
+            response.id.with(idx),
+            egui::Sense::click_and_drag(),
+        );
+
+        if point_response.dragged() {
+            let dragged = from_screen * (center + point_response.drag_delta());
+            let new_x = if idx == 0 || idx == last {
+                x
+            } else {
+                dragged
+                    .x
+                    .max(points[idx - 1].0 + CUSTOM_CURVE_MIN_SPACING)
+                    .min(points[idx + 1].0 - CUSTOM_CURVE_MIN_SPACING)
+            };
+            points[idx] = (new_x, dragged.y.clamp(-1.0, 1.0));
+            changed = true;
+        }
+
+        if point_response.secondary_clicked() && idx != 0 && idx != last {
+            removed = Some(idx);
+        }
+    }
+
+    if let Some(idx) = removed {
+        points.remove(idx);
+        changed = true;
+    }
+
+    if response.double_clicked()
+        && let Some(pointer) = response.interact_pointer_pos()
+    {
+        let new_point = from_screen * pointer;
+        if let Some(idx) = points.iter().position(|&(x, _)| x > new_point.x) {
+            let has_room = idx > 0
+                && new_point.x - points[idx - 1].0 >= CUSTOM_CURVE_MIN_SPACING
+                && points[idx].0 - new_point.x >= CUSTOM_CURVE_MIN_SPACING;
+            if has_room {
+                points.insert(idx, (new_point.x, new_point.y.clamp(-1.0, 1.0)));
+                changed = true;
+            }
+        }
+    }
+
+    if changed {
+        *params.custom_curve.write() = points.clone();
+        params.custom_curve_changed.store(true, Ordering::Relaxed);
+    }
+
+    // Axes and the unity line for reference
+    painter.line_segment(
+        [response.rect.left_center(), response.rect.right_center()],
+        Stroke::new(1.0, axis_color),
+    );
+    painter.line_segment(
+        [response.rect.center_top(), response.rect.center_bottom()],
+        Stroke::new(1.0, axis_color),
+    );
+    painter.line_segment(
+        [response.rect.left_bottom(), response.rect.right_top()],
+        Stroke::new(1.0, axis_color),
+    );
+
+    let curve = (0..=256)
+        .map(|idx| {
+            let x = (idx as f32 / 256.0) * 2.0 - 1.0;
+            to_screen * Pos2::new(x, custom_curve_spline(&points, x).clamp(-1.0, 1.0))
+        })
+        .collect::<Vec<Pos2>>();
+    painter.add(egui::epaint::Shape::line(
+        curve,
+        Stroke::new(2.0, Color32::LIGHT_BLUE),
+    ));
+
+    for &(x, y) in points.iter() {
+        painter.circle_filled(to_screen * Pos2::new(x, y), point_radius, Color32::WHITE);
+    }
 }
 
-pub fn distortion_plugin_process(buffer: &mut Buffer, params: &Arc<PluginParams>) -> ProcessStatus {
-    let num_samples = buffer.samples();
-    let output = buffer.as_slice();
-    let distortion_mode = params.distortion_mode.value();
-    let distortion_amount = params.distortion_amount.value();
-    let distortion_mix = params.distortion_mix.value();
-    let bit_crush_bits = params.bit_crush_bits.value();
+// Points on the drawn transfer curve
+const DISTORTION_CURVE_POINTS: usize = 256;
+
+/// Everything the distortion visualization depends on. The curve and harmonics are only computed
+/// again when this changes.
+#[derive(Clone, PartialEq)]
+struct DistortionVisualizationKey {
+    band: usize,
+    path: Option<usize>,
+    mode: DistortionMode,
+    amount: f32,
+    bit_crush_bits: f32,
+    triode_bias: f32,
+    fold_count: f32,
+    drive: f32,
+    band_drive: f32,
+    band_mix: f32,
+    bias: f32,
+    auto_gain: bool,
+    custom_curve: Vec<(f32, f32)>,
+}
 
-    let gain = distortion_mix / 100.0;
+impl DistortionVisualizationKey {
+    fn new(params: &PluginParams, band: usize, path: Option<usize>) -> Self {
+        let mode = params.distortion_band_mode(band);
+        let (amount, drive) = params.distortion_amount_drive(path);
+        let (band_drive, band_mix) = if params.distortion_band_count() > 1 {
+            let band_params = &params.distortion_bands[band];
+            (band_params.drive.value(), band_params.mix.value())
+        } else {
+            (0.0, 100.0)
+        };
+        let custom_curve = if mode == DistortionMode::Custom {
+            params.custom_curve.read().clone()
+        } else {
+            Vec::new()
+        };
 
-    for sample_idx in 0..num_samples {
-        // l/r channels
-        let mut l = output[0][sample_idx];
-        let mut r = output[1][sample_idx];
+        Self {
+            band,
+            path,
+            mode,
+            amount,
+            bit_crush_bits: params.bit_crush_bits.value(),
+            triode_bias: params.triode_bias.value(),
+            fold_count: params.fold_count.value(),
+            drive,
+            band_drive,
+            band_mix,
+            bias: params.distortion_bias.value(),
+            auto_gain: params.distortion_auto_gain.value(),
+            custom_curve,
+        }
+    }
+}
 
-        // apply distortion algorithm
-        // This might not be the best because this match happens every sample.
-        match distortion_mode {
-            DistortionMode::InfiniteClipping => {
-                if l > 0.0 {
-                    l = lerp(l, 1.0, distortion_amount);
-                } else if l < 0.0 {
-                    l = -lerp(-l, 1.0, distortion_amount);
-                }
+/// Editor side state of the distortion visualization, the band and mid/side path that's shown and
+/// the cached curve and harmonics for it.
+#[derive(Clone, Default)]
+pub struct DistortionDisplay {
+    band: usize,
+    path: usize,
+    key: Option<DistortionVisualizationKey>,
+    curve: Vec<f32>,
+    harmonics: [f32; DISTORTION_HARMONICS],
+}
+
+impl DistortionDisplay {
+    fn update(&mut self, params: &PluginParams) {
+        // Without mid/side processing both channels run with the main settings
+        let path = params.mid_side_processing.value().then_some(self.path);
+        let key = DistortionVisualizationKey::new(params, self.band, path);
+        if self.key.as_ref() == Some(&key) {
+            return;
+        }
+
+        let mut custom_curve = TransferCurve::default();
+        if key.mode == DistortionMode::Custom {
+            custom_curve.bake(&key.custom_curve);
+        }
+        let transfer = StaticDistortion::new(params, self.band, path, &key.mode, &custom_curve);
+        self.curve = (0..=DISTORTION_CURVE_POINTS)
+            .map(|idx| transfer.process((idx as f32 / DISTORTION_CURVE_POINTS as f32) * 2.0 - 1.0))
+            .collect();
+        self.harmonics = transfer.harmonics();
+        self.key = Some(key);
+    }
+}
+
+/// Plots the distortion's static transfer curve next to the harmonics it adds to a full scale
+/// sine. Both are computed on the GUI thread, and only again when the
+/// `DistortionVisualizationKey` changes.
+pub fn distortion_visualization(
+    ui: &mut egui::Ui,
+    params: &PluginParams,
+    display: &mut DistortionDisplay,
+) {
+    let axis_color = Color32::from_additive_luminance(64);
+
+    let band_count = params.distortion_band_count();
+    display.band = display.band.min(band_count - 1);
+    let mid_side = params.mid_side_processing.value();
+    if band_count > 1 || mid_side {
+        ui.horizontal(|ui| {
+            if mid_side {
+                ui.selectable_value(&mut display.path, 0, "Mid");
+                ui.selectable_value(&mut display.path, 1, "Side");
+            }
 
-                if r > 0.0 {
-                    r = lerp(r, 1.0, distortion_amount);
-                } else if l < 0.0 {
-                    r = -lerp(-r, 1.0, distortion_amount);
+            if band_count > 1 {
+                for band in 0..band_count {
+                    if ui
+                        .add(egui::widgets::SelectableLabel::new(
+                            display.band == band,
+                            format!("Band {}", band + 1),
+                        ))
+                        .clicked()
+                    {
+                        display.band = band;
+                    }
                 }
             }
-            DistortionMode::Cubic => {
-                l = l - distortion_amount * (1.0 / 3.0) * l * l * l;
-                r = r - distortion_amount * (1.0 / 3.0) * r * r * r;
+        });
+    }
+
+    display.update(params);
+    let harmonics = &display.harmonics;
+
+    ui.horizontal(|ui| {
+        let plot_size = (ui.available_width() / 2.0 - 8.0).clamp(100.0, 240.0);
+
+        ui.vertical(|ui| {
+            ui.label("Transfer Curve");
+            let (response, painter) =
+                ui.allocate_painter(vec2(plot_size, plot_size), egui::Sense::hover());
+            let rect = response.rect;
+            let to_screen =
+                emath::RectTransform::from_to(Rect::from_x_y_ranges(-1.0..=1.0, 1.0..=-1.0), rect);
+
+            painter.line_segment(
+                [rect.left_center(), rect.right_center()],
+                Stroke::new(1.0, axis_color),
+            );
+            painter.line_segment(
+                [rect.center_top(), rect.center_bottom()],
+                Stroke::new(1.0, axis_color),
+            );
+
+            let curve = display
+                .curve
+                .iter()
+                .enumerate()
+                .map(|(idx, y)| {
+                    let x = (idx as f32 / DISTORTION_CURVE_POINTS as f32) * 2.0 - 1.0;
+                    to_screen * Pos2::new(x, y.clamp(-1.2, 1.2))
+                })
+                .collect::<Vec<Pos2>>();
+            painter.add(egui::epaint::Shape::line(
+                curve,
+                Stroke::new(2.0, Color32::LIGHT_BLUE),
+            ));
+        });
+
+        ui.vertical(|ui| {
+            ui.label("Harmonics");
+            let (response, painter) =
+                ui.allocate_painter(vec2(plot_size, plot_size), egui::Sense::hover());
+            let rect = response.rect;
+            let bar_width = rect.width() / DISTORTION_HARMONICS as f32;
+
+            for (idx, amplitude) in harmonics.iter().enumerate() {
+                // Bars span -96 dB to 0 dB
+                let level = ((util::gain_to_db(*amplitude) + 96.0) / 96.0).clamp(0.0, 1.0);
+                let left = rect.left() + idx as f32 * bar_width;
+                let bar = Rect::from_min_max(
+                    Pos2::new(left + 1.0, rect.bottom() - level * rect.height()),
+                    Pos2::new(left + bar_width - 1.0, rect.bottom()),
+                );
+                let color = if idx == 0 {
+                    Color32::LIGHT_BLUE
+                } else {
+                    Color32::from_rgb(255, 170, 80)
+                };
+                painter.rect_filled(bar, 0.0, color);
             }
-            // With arctangent and exponential soft clipping,
-            // I wonder if setting the gain or alpha to the lowest setting should return the original sample?
-            DistortionMode::ArcTangent => {
-                let alpha = (distortion_amount * 10.0).max(1.0);
-                l = (2.0 / std::f32::consts::PI) * (l * alpha).atan();
-                r = (2.0 / std::f32::consts::PI) * (r * alpha).atan();
+
+            let distortion_power = harmonics[1..].iter().map(|h| h * h).sum::<f32>();
+            let thd = if harmonics[0] > 0.0 {
+                100.0 * distortion_power.sqrt() / harmonics[0]
+            } else {
+                0.0
+            };
+            ui.label(format!("THD: {thd:.2}%"));
+        });
+    });
+}
+
+pub fn lerp(start: f32, end: f32, amount: f32) -> f32 {
+    start * (1.0 - amount) + end * amount
+}
+
+/// Everything the shapers need besides the input sample.
+#[derive(Clone, Copy)]
+pub struct ShaperSettings<'a> {
+    pub mode: &'a DistortionMode,
+    pub amount: f32,
+    pub bit_crush_bits: f32,
+    pub custom_curve: &'a TransferCurve,
+    pub triode_bias: f32,
+    pub fold_count: f32,
+}
+
+/// Applies a single `DistortionMode` transfer function to one sample.
+pub fn distortion_shaper(sample: f32, settings: &ShaperSettings) -> f32 {
+    let distortion_amount = settings.amount;
+    match settings.mode {
+        DistortionMode::InfiniteClipping => {
+            if sample > 0.0 {
+                lerp(sample, 1.0, distortion_amount)
+            } else if sample < 0.0 {
+                -lerp(-sample, 1.0, distortion_amount)
+            } else {
+                sample
             }
-            DistortionMode::ExponentialSoftClipping => {
-                let gain = (distortion_amount * 10.0).max(1.0);
-                l = (l / l.abs()) * (1.0 - (-(gain * l).abs()).exp());
-                r = (r / r.abs()) * (1.0 - (-(gain * r).abs()).exp());
+        }
+        DistortionMode::Cubic => {
+            sample - distortion_amount * (1.0 / 3.0) * sample * sample * sample
+        }
+        // With arctangent and exponential soft clipping,
+        // I wonder if setting the gain or alpha to the lowest setting should return the original sample?
+        DistortionMode::ArcTangent => {
+            let alpha = (distortion_amount * 10.0).max(1.0);
+            (2.0 / std::f32::consts::PI) * (sample * alpha).atan()
+        }
+        DistortionMode::ExponentialSoftClipping => {
+            let gain = (distortion_amount * 10.0).max(1.0);
+            // `signum()` instead of `x / |x|` so silence doesn't turn into NaN
+            sample.signum() * (1.0 - (-(gain * sample).abs()).exp())
+        }
+        DistortionMode::PieceWiseOverDrive => {
+            if sample.abs() <= 1.0 / 3.0 {
+                2.0 * sample
+            } else if sample.abs() > 2.0 / 3.0 {
+                sample / sample.abs()
+            } else {
+                (sample / sample.abs())
+                    * ((3.0 - (2.0 - 3.0 * sample.abs()) * (2.0 - 3.0 * sample.abs())) / 3.0)
             }
-            DistortionMode::PieceWiseOverDrive => {
-                if l.abs() <= 1.0 / 3.0 {
-                    l = 2.0 * l;
-                } else if l.abs() > 2.0 / 3.0 {
-                    l = l / l.abs();
-                } else {
-                    l = (l / l.abs())
-                        * ((3.0 - (2.0 - 3.0 * l.abs()) * (2.0 - 3.0 * l.abs())) / 3.0);
-                }
+        }
+        DistortionMode::Diode => {
+            let thermal_voltage = 0.0253;
+            let emission_coefficient = 1.68;
+            let saturation_current = 0.105;
 
-                if r.abs() <= 1.0 / 3.0 {
-                    r = 2.0 * r;
-                } else if r.abs() > 2.0 / 3.0 {
-                    r = r / r.abs();
-                } else {
-                    r = (r / r.abs())
-                        * ((3.0 - (2.0 - 3.0 * r.abs()) * (2.0 - 3.0 * r.abs())) / 3.0);
-                }
+            saturation_current
+                * ((0.1 * sample / (emission_coefficient * thermal_voltage)).exp() - 1.0)
+        }
+        DistortionMode::BitCrush => {
+            // Fractional bit depths just give a non power of two number of steps
+            let amplitude_values = 2.0_f32.powf(settings.bit_crush_bits);
+            let normalized = (0.5 * sample) + 0.5;
+
+            2.0 * ((normalized * amplitude_values).round() / amplitude_values) - 1.0
+        }
+        // The amount fades from a clean signal to the full curve like it does for the other modes
+        DistortionMode::Custom => lerp(
+            sample,
+            settings.custom_curve.lookup(sample),
+            distortion_amount,
+        ),
+        // Grid conduction softly limits the positive swing, while the negative swing runs into
+        // cutoff. A colder (negative) bias reaches cutoff sooner, which makes the curve more
+        // asymmetric and adds more even harmonics.
+        DistortionMode::Triode => {
+            let grid = sample * (1.0 + 9.0 * distortion_amount);
+            if grid >= 0.0 {
+                grid.tanh()
+            } else {
+                let cutoff = 1.0 + 0.5 * settings.triode_bias;
+                cutoff * (grid / cutoff).tanh()
             }
-            DistortionMode::Diode => {
-                let thermal_voltage = 0.0253;
-                let emission_coefficient = 1.68;
-                let saturation_current = 0.105;
-
-                l = saturation_current
-                    * ((0.1 * l / (emission_coefficient * thermal_voltage)).exp() - 1.0);
-                r = saturation_current
-                    * ((0.1 * r / (emission_coefficient * thermal_voltage)).exp() - 1.0);
+        }
+        // The anhysteretic magnetization curve, `Tape` adds the hysteresis on top of this
+        DistortionMode::Tape => (sample * (1.0 + 4.0 * distortion_amount)).tanh(),
+        // A full scale input folds over `fold_count` times
+        DistortionMode::SineFold => {
+            let folded = (std::f32::consts::FRAC_PI_2 * settings.fold_count * sample).sin();
+            lerp(sample, folded, distortion_amount)
+        }
+        DistortionMode::TriangleFold => {
+            // Reflects off of -1 and 1, so it's the identity until the first fold
+            let phase = settings.fold_count * sample + 1.0;
+            let folded = 1.0 - (phase.rem_euclid(4.0) - 2.0).abs();
+            lerp(sample, folded, distortion_amount)
+        }
+        DistortionMode::HalfWaveRectifier => lerp(sample, sample.max(0.0), distortion_amount),
+        DistortionMode::FullWaveRectifier => lerp(sample, sample.abs(), distortion_amount),
+    }
+}
+
+// Harmonics shown in the distortion panel, starting at the fundamental
+pub const DISTORTION_HARMONICS: usize = 16;
+
+// The test sine is a single period long so every harmonic lands exactly on a DFT bin
+const HARMONICS_TEST_LENGTH: usize = 1024;
+
+/// The memoryless part of the distortion for one band, meaning drive, bias, the shaper, the band
+/// mix and the makeup gain. The filters, the crossover and the oversampling are left out, so this
+/// is what the editor visualizes rather than exactly what the audio thread outputs.
+pub struct StaticDistortion<'a> {
+    settings: ShaperSettings<'a>,
+    drive: f32,
+    band_drive: f32,
+    band_makeup_gain: f32,
+    band_mix: f32,
+    makeup_gain: f32,
+    bias: f32,
+    bias_offset: f32,
+}
+
+impl<'a> StaticDistortion<'a> {
+    /// `path` is the mid/side path to show in mid/side processing, see
+    /// [`PluginParams::distortion_amount_drive()`].
+    pub fn new(
+        params: &PluginParams,
+        band: usize,
+        path: Option<usize>,
+        distortion_mode: &'a DistortionMode,
+        custom_curve: &'a TransferCurve,
+    ) -> Self {
+        let (amount, drive) = params.distortion_amount_drive(path);
+        let settings = ShaperSettings {
+            mode: distortion_mode,
+            amount,
+            bit_crush_bits: params.bit_crush_bits.value(),
+            custom_curve,
+            triode_bias: params.triode_bias.value(),
+            fold_count: params.fold_count.value(),
+        };
+        let auto_gain = params.distortion_auto_gain.value();
+        let drive = db_to_gain(drive);
+        let (band_drive, band_mix) = if params.distortion_band_count() > 1 {
+            let band_params = &params.distortion_bands[band];
+            (
+                db_to_gain(band_params.drive.value()),
+                band_params.mix.value() / 100.0,
+            )
+        } else {
+            (1.0, 1.0)
+        };
+        let bias = params.distortion_bias.value();
+
+        Self {
+            settings,
+            drive,
+            band_drive,
+            band_makeup_gain: if auto_gain { 1.0 / band_drive } else { 1.0 },
+            band_mix,
+            makeup_gain: if auto_gain { 1.0 / drive } else { 1.0 },
+            bias,
+            bias_offset: distortion_shaper(bias, &settings),
+        }
+    }
+
+    pub fn process(&self, sample: f32) -> f32 {
+        let driven = sample * self.drive;
+        let shaped = distortion_shaper(driven * self.band_drive + self.bias, &self.settings)
+            - self.bias_offset;
+
+        lerp(driven, shaped * self.band_makeup_gain, self.band_mix) * self.makeup_gain
+    }
+
+    /// Amplitudes of the harmonics a full scale sine picks up, the first entry is the fundamental.
+    /// DC is left out since the audio path removes it anyway.
+    pub fn harmonics(&self) -> [f32; DISTORTION_HARMONICS] {
+        let mut output = [0.0; HARMONICS_TEST_LENGTH];
+        for (idx, sample) in output.iter_mut().enumerate() {
+            let phase = std::f32::consts::TAU * idx as f32 / HARMONICS_TEST_LENGTH as f32;
+            *sample = self.process(phase.sin());
+        }
+
+        let mut harmonics = [0.0; DISTORTION_HARMONICS];
+        for (harmonic, amplitude) in harmonics.iter_mut().enumerate() {
+            let bin = (harmonic + 1) as f32;
+            let (mut real, mut imaginary) = (0.0, 0.0);
+            for (idx, sample) in output.iter().enumerate() {
+                let phase = std::f32::consts::TAU * bin * idx as f32 / HARMONICS_TEST_LENGTH as f32;
+                real += sample * phase.cos();
+                imaginary += sample * phase.sin();
             }
-            DistortionMode::BitCrush => {
-                let amplitude_values = 2_u32.pow(bit_crush_bits.try_into().unwrap());
+            *amplitude =
+                2.0 * (real * real + imaginary * imaginary).sqrt() / HARMONICS_TEST_LENGTH as f32;
+        }
+
+        harmonics
+    }
+}
 
-                l = (0.5 * l) + 0.5;
-                r = (0.5 * r) + 0.5;
+pub fn distortion_plugin_process(
+    sample_rate: f32,
+    buffer: &mut Buffer,
+    params: &Arc<PluginParams>,
+    mid_side: bool,
+    distortion: &mut Distortion,
+) -> ProcessStatus {
+    let num_samples = buffer.samples();
+    let output = buffer.as_slice();
+    let bit_crush_bits = params.bit_crush_bits.value();
+    let oversampling_stages = params.distortion_oversampling.value().stages();
+    let band_count = params.distortion_band_count();
+    let band_modes: [DistortionMode; MAX_DISTORTION_BANDS] =
+        std::array::from_fn(|band| params.distortion_band_mode(band));
+
+    // The editor never blocks on this, so if it's holding the lock the table is rebaked next block
+    if params.custom_curve_changed.swap(false, Ordering::Relaxed) {
+        match params.custom_curve.try_read() {
+            Some(points) => distortion.custom_curve.bake(&points),
+            None => params.custom_curve_changed.store(true, Ordering::Relaxed),
+        }
+    }
 
-                l = 2.0 * ((l * amplitude_values as f32).round() / amplitude_values as f32) - 1.0;
-                r = 2.0 * ((r * amplitude_values as f32).round() / amplitude_values as f32) - 1.0;
+    // Stale filter state from a different rate would otherwise ring out after switching
+    if oversampling_stages != distortion.oversampling_stages {
+        distortion.oversampling_stages = oversampling_stages;
+        for channel in distortion.channels.iter_mut() {
+            for band in channel.bands.iter_mut() {
+                band.oversampler.reset();
             }
         }
+    }
+
+    if band_count != distortion.band_count {
+        distortion.band_count = band_count;
+        for channel in distortion.channels.iter_mut() {
+            channel.crossover.reset();
+        }
+    }
+
+    // Left and right share the settings, mid and side each have their own
+    let mut distortion_amount = [params.distortion_amount.value(); 2];
+    let mut distortion_drive = [params.distortion_drive.value(); 2];
+    let mut distortion_mix = [params.distortion_mix.value(); 2];
+    if mid_side {
+        for (path_idx, path) in params.mid_side_paths.iter().enumerate() {
+            distortion_amount[path_idx] = path.distortion_amount.value();
+            distortion_drive[path_idx] = path.distortion_drive.value();
+            distortion_mix[path_idx] = path.distortion_mix.value();
+        }
+    }
+
+    // The drive is the same in dB for every mode, `distortion_amount` only changes the curve
+    let auto_gain = params.distortion_auto_gain.value();
+    let drive = distortion_drive.map(db_to_gain);
+    let makeup_gain = drive.map(|drive| if auto_gain { 1.0 / drive } else { 1.0 });
+    let output_gain = db_to_gain(params.distortion_output.value());
+    let emphasis = params.distortion_emphasis.value();
+    let tone = params.distortion_tone.value();
+
+    // Matching the host rate makes the decimator pass the signal through untouched
+    let bit_crush_rate = match params.bit_crush_rate.value() {
+        rate if rate >= BIT_CRUSH_RATE_OFF => sample_rate,
+        rate => rate,
+    };
+    let tape_speed = params.tape_speed.value();
+
+    let mut crossover_frequencies = [
+        params.distortion_crossover_low.value(),
+        params.distortion_crossover_mid.value(),
+        params.distortion_crossover_high.value(),
+    ];
+    crossover_frequencies.sort_unstable_by(|a, b| a.total_cmp(b));
+
+    for channel_state in distortion.channels.iter_mut() {
+        channel_state.dc_blocker.set_sample_rate(sample_rate);
+        channel_state.pre_emphasis.set_tilt(sample_rate, emphasis);
+        channel_state.de_emphasis.set_tilt(sample_rate, -emphasis);
+        channel_state.tone.set_tilt(sample_rate, tone);
+        channel_state
+            .crossover
+            .set_frequencies(sample_rate, &crossover_frequencies);
+        for band in channel_state.bands.iter_mut().take(band_count) {
+            band.decimator.set_prefilter(sample_rate, bit_crush_rate);
+            band.tape.set_speed(sample_rate, tape_speed);
+        }
+    }
+
+    let latency = params.distortion_latency() as usize;
+    let channel_band_settings = |channel: usize, band: usize| {
+        let shaper = ShaperSettings {
+            mode: &band_modes[band],
+            amount: distortion_amount[channel],
+            bit_crush_bits,
+            custom_curve: &distortion.custom_curve,
+            triode_bias: params.triode_bias.value(),
+            fold_count: params.fold_count.value(),
+        };
+        let antialiasing = params.antialiasing(&band_modes[band]);
+
+        // Without multiband processing the main drive and mix cover everything
+        let (band_drive, band_mix) = if band_count > 1 {
+            let band_params = &params.distortion_bands[band];
+            (
+                db_to_gain(band_params.drive.value()),
+                band_params.mix.value() / 100.0,
+            )
+        } else {
+            (1.0, 1.0)
+        };
+
+        // Shifting the operating point makes every shaper asymmetric, which adds even harmonics.
+        // The shaper's output at the bias point is subtracted so silence stays silent, and the DC
+        // blocker takes care of the offset the asymmetry itself introduces.
+        let bias = params.distortion_bias.value();
+
+        BandSettings {
+            shaper,
+            antialiasing,
+            drive: band_drive,
+            makeup_gain: if auto_gain { 1.0 / band_drive } else { 1.0 },
+            bias,
+            bias_offset: distortion_shaper(bias, &shaper),
+            mix: band_mix,
+            oversampling_stages,
+            latency,
+            alignment: latency - Distortion::latency(oversampling_stages, antialiasing) as usize,
+            sample_rate,
+            bit_crush_rate,
+            bit_crush_jitter: params.bit_crush_jitter.value(),
+            bit_crush_prefilter: params.bit_crush_prefilter.value(),
+            tape_hysteresis: params.tape_hysteresis.value(),
+        }
+    };
+    let band_settings: [[BandSettings; MAX_DISTORTION_BANDS]; 2] = std::array::from_fn(|channel| {
+        std::array::from_fn(|band| channel_band_settings(channel, band))
+    });
 
-        // adjust gain using parallel mix wet/dry
-        l = gain * l + (1.0 - gain) * output[0][sample_idx];
-        r = gain * r + (1.0 - gain) * output[1][sample_idx];
+    let gain = distortion_mix.map(|distortion_mix| distortion_mix / 100.0);
 
-        // write back to output buffer
-        output[0][sample_idx] = l;
-        output[1][sample_idx] = r;
+    for sample_idx in 0..num_samples {
+        for (channel_idx, (channel_state, channel)) in distortion
+            .channels
+            .iter_mut()
+            .zip(output.iter_mut())
+            .enumerate()
+        {
+            let band_settings = &band_settings[channel_idx];
+            let dry = channel_state
+                .dry_delay
+                .process(channel[sample_idx], latency);
+            let driven = channel_state
+                .pre_emphasis
+                .process(channel[sample_idx] * drive[channel_idx]);
+
+            let shaped = if band_count > 1 {
+                let split = channel_state.crossover.split(driven, band_count);
+                channel_state
+                    .bands
+                    .iter_mut()
+                    .zip(split)
+                    .zip(band_settings.iter())
+                    .take(band_count)
+                    .map(|((band, sample), settings)| band.process(sample, settings))
+                    .sum()
+            } else {
+                channel_state.bands[0].process(driven, &band_settings[0])
+            };
+
+            let centered = channel_state
+                .dc_blocker
+                .process(channel_state.de_emphasis.process(shaped));
+            let wet = channel_state.tone.process(centered) * makeup_gain[channel_idx];
+
+            // adjust gain using parallel mix wet/dry
+            let gain = gain[channel_idx];
+            channel[sample_idx] = (gain * wet + (1.0 - gain) * dry) * output_gain;
+        }
     }
 
     // match distortion_mode {
@@ -1336,8 +7330,8 @@ pub fn mid_side_encode_plugin_process(
     let output = buffer.as_slice();
 
     for sample_idx in 0..num_samples {
-        let mid = (2.0 - stereo_width) * (output[0][sample_idx] + output[1][sample_idx]) * 0.5;
-        let side = stereo_width * (output[0][sample_idx] - output[1][sample_idx]) * 0.5;
+        let (mid, side) =
+            mid_side_encode(output[0][sample_idx], output[1][sample_idx], stereo_width);
 
         output[0][sample_idx] = mid;
         output[1][sample_idx] = side;
@@ -1354,8 +7348,7 @@ pub fn mid_side_decode_plugin_process(
     let output = buffer.as_slice();
 
     for sample_idx in 0..num_samples {
-        let left = output[0][sample_idx] + output[1][sample_idx];
-        let right = output[0][sample_idx] - output[1][sample_idx];
+        let (left, right) = mid_side_decode(output[0][sample_idx], output[1][sample_idx]);
 
         output[0][sample_idx] = left;
         output[1][sample_idx] = right;
@@ -1364,6 +7357,199 @@ pub fn mid_side_decode_plugin_process(
     ProcessStatus::Normal
 }
 
+/// Splits left and right into mid and side. A width of 1 leaves the image as is, so decoding
+/// gives back the original signal, while 0 collapses it to mono and 2 leaves only the sides.
+pub fn mid_side_encode(left: f32, right: f32, stereo_width: f32) -> (f32, f32) {
+    let mid = (2.0 - stereo_width) * (left + right) * 0.5;
+    let side = stereo_width * (left - right) * 0.5;
+
+    (mid, side)
+}
+
+/// Inverse of [`mid_side_encode`] with a width of 1.
+pub fn mid_side_decode(mid: f32, side: f32) -> (f32, f32) {
+    (mid + side, mid - side)
+}
+
+/// Haas widener, delays one channel by a few milliseconds so the source spreads out between the
+/// speakers while still being heard from the side that arrives first.
+pub fn haas_plugin_process(
+    sample_rate: f32,
+    buffer: &mut Buffer,
+    params: &Arc<PluginParams>,
+    view_settings: &ViewSettings,
+    haas: &mut Haas,
+    haas_meter_input: &mut triple_buffer::Input<StereoMeterReadout>,
+) -> ProcessStatus {
+    let num_samples = buffer.samples();
+    let output = buffer.as_slice();
+
+    // There's no second channel to delay with the mono layout
+    if output.len() < 2 {
+        return ProcessStatus::Normal;
+    }
+
+    let buffer_len = haas.delay[0].len();
+    if buffer_len == 0 {
+        return ProcessStatus::Normal;
+    }
+
+    let delay_samples =
+        ((sample_rate * (params.haas_delay.value() / 1000.0)) as usize).min(buffer_len - 1);
+    let level = db_to_gain(params.haas_level.value());
+    let filter = params.haas_filter.value();
+    haas.highpass.set_highpass(
+        sample_rate,
+        params.haas_highpass.value(),
+        std::f32::consts::FRAC_1_SQRT_2,
+    );
+    haas.lowpass.set_lowpass(
+        sample_rate,
+        params.haas_lowpass.value().min(sample_rate * 0.45),
+        std::f32::consts::FRAC_1_SQRT_2,
+    );
+    haas.stereo_meter
+        .set_integration_time(sample_rate, view_settings.stereo_meter_integration);
+
+    // The filters' state belongs to the channel that was delayed before
+    let haas_channel = params.haas_channel.value();
+    if haas.channel != Some(haas_channel) {
+        haas.channel = Some(haas_channel);
+        haas.highpass.reset();
+        haas.lowpass.reset();
+    }
+
+    let [left_delay, right_delay] = &mut haas.delay;
+    let (left_channel, right_channel) = output.split_at_mut(1);
+    for (sample_idx, (left, right)) in left_channel[0]
+        .iter_mut()
+        .zip(right_channel[0].iter_mut())
+        .take(num_samples)
+        .enumerate()
+    {
+        let write_idx = (haas.delay_index + sample_idx) % buffer_len;
+        let read_idx = (write_idx + buffer_len - delay_samples) % buffer_len;
+
+        left_delay[write_idx] = *left;
+        right_delay[write_idx] = *right;
+
+        let (sample, delay_buffer) = match haas_channel {
+            HaasChannel::Left => (&mut *left, &*left_delay),
+            HaasChannel::Right => (&mut *right, &*right_delay),
+        };
+        let mut delayed = delay_buffer[read_idx];
+        if filter {
+            delayed = haas.lowpass.process(haas.highpass.process(delayed));
+        }
+        *sample = delayed * level;
+
+        haas.stereo_meter.process(*left, *right);
+    }
+
+    haas.delay_index = (haas.delay_index + num_samples) % buffer_len;
+    haas_meter_input.write(haas.stereo_meter.readout());
+
+    ProcessStatus::Normal
+}
+
+/// Multiband stereo width with an optional mono low end.
+pub fn imager_plugin_process(
+    sample_rate: f32,
+    buffer: &mut Buffer,
+    params: &Arc<PluginParams>,
+    imager: &mut Imager,
+) -> ProcessStatus {
+    let num_samples = buffer.samples();
+    let output = buffer.as_slice();
+
+    // There's no stereo image to change with the mono layout
+    if output.len() < 2 {
+        return ProcessStatus::Normal;
+    }
+
+    let mut crossover_frequencies = [
+        params.imager_crossover_low.value(),
+        params.imager_crossover_mid.value(),
+        params.imager_crossover_high.value(),
+    ];
+    crossover_frequencies.sort_unstable_by(|a, b| a.total_cmp(b));
+    imager.set_frequencies(
+        sample_rate,
+        &crossover_frequencies,
+        params.imager_bass_mono_frequency.value(),
+    );
+
+    let settings = ImagerSettings {
+        band_count: params.imager_band_count.value() as usize,
+        widths: std::array::from_fn(|band| params.imager_bands[band].width.value() / 100.0),
+        bass_mono: params.imager_bass_mono.value(),
+    };
+
+    let (left_channel, right_channel) = output.split_at_mut(1);
+    for (left, right) in left_channel[0]
+        .iter_mut()
+        .zip(right_channel[0].iter_mut())
+        .take(num_samples)
+    {
+        (*left, *right) = imager.process(*left, *right, &settings);
+    }
+
+    ProcessStatus::Normal
+}
+
+/// Monitoring utility for checking a mix, soloing mid or side, swapping the channels, folding down
+/// to mono and flipping the polarity of either channel.
+pub fn monitor_plugin_process(buffer: &mut Buffer, params: &Arc<PluginParams>) -> ProcessStatus {
+    let num_samples = buffer.samples();
+    let monitor_mode = params.monitor_mode.value();
+    let left_polarity = if params.monitor_invert_left.value() {
+        -1.0
+    } else {
+        1.0
+    };
+    let right_polarity = if params.monitor_invert_right.value() {
+        -1.0
+    } else {
+        1.0
+    };
+    let output = buffer.as_slice();
+
+    // Nothing to solo or swap with the mono layout
+    if output.len() < 2 {
+        return ProcessStatus::Normal;
+    }
+
+    let (left_channel, right_channel) = output.split_at_mut(1);
+    for (left_sample, right_sample) in left_channel[0]
+        .iter_mut()
+        .zip(right_channel[0].iter_mut())
+        .take(num_samples)
+    {
+        let left = *left_sample;
+        let right = *right_sample;
+        let (mid, side) = mid_side_encode(left, right, 1.0);
+
+        let (left, right) = match monitor_mode {
+            MonitorMode::Stereo => (left, right),
+            MonitorMode::Mid => mid_side_decode(mid, 0.0),
+            // Side comes out of the speakers in opposite polarity, just like it does in the mix
+            MonitorMode::Side => mid_side_decode(0.0, side),
+            MonitorMode::Swap => (right, left),
+            // Unlike the mid solo this folds down with the -3 dB pan law, so uncorrelated material
+            // keeps its level the way it would on a mono playback system
+            MonitorMode::Mono => {
+                let mono = (left + right) * std::f32::consts::FRAC_1_SQRT_2;
+                (mono, mono)
+            }
+        };
+
+        *left_sample = left * left_polarity;
+        *right_sample = right * right_polarity;
+    }
+
+    ProcessStatus::Normal
+}
+
 impl ClapPlugin for HackAudio {
     const CLAP_ID: &'static str = "com.notryanb-plugins-.hack-audio";
     const CLAP_DESCRIPTION: Option<&'static str> = Some("Implementations of FX in HackAudio book");
@@ -1385,3 +7571,241 @@ impl Vst3Plugin for HackAudio {
 
 nih_export_clap!(HackAudio);
 nih_export_vst3!(HackAudio);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shaper_settings<'a>(
+        mode: &'a DistortionMode,
+        amount: f32,
+        custom_curve: &'a TransferCurve,
+    ) -> ShaperSettings<'a> {
+        ShaperSettings {
+            mode,
+            amount,
+            bit_crush_bits: 16.0,
+            custom_curve,
+            triode_bias: 0.0,
+            fold_count: 1.0,
+        }
+    }
+
+    /// Energy outside of the fundamental and DC for a bin aligned sine pushed through the shaper.
+    /// Every harmonic of 15 kHz lies above Nyquist at 48 kHz, so all of that energy is aliasing.
+    fn aliased_energy(antialiasing: Antialiasing, settings: &ShaperSettings, gain: f32) -> f32 {
+        const SIZE: usize = 4096;
+        const FUNDAMENTAL_BIN: usize = 1280;
+        const WARMUP: usize = 64;
+
+        let mut adaa = Adaa::default();
+        let mut real = vec![0.0; SIZE];
+        let mut imag = vec![0.0; SIZE];
+        for idx in 0..SIZE + WARMUP {
+            let phase = std::f64::consts::TAU * (FUNDAMENTAL_BIN * idx) as f64 / SIZE as f64;
+            let output = adaa.process(gain * phase.sin() as f32, antialiasing, settings);
+            if idx >= WARMUP {
+                real[idx - WARMUP] = output;
+            }
+        }
+        Fft::default().process(&mut real, &mut imag);
+
+        (1..SIZE / 2)
+            .filter(|bin| *bin != FUNDAMENTAL_BIN)
+            .map(|bin| real[bin] * real[bin] + imag[bin] * imag[bin])
+            .sum()
+    }
+
+    #[test]
+    fn adaa_reduces_aliasing() {
+        let custom_curve = TransferCurve::default();
+        for (mode, amount, gain) in [
+            (DistortionMode::ArcTangent, 0.5, 4.0),
+            (DistortionMode::Cubic, 1.0, 1.0),
+            (DistortionMode::InfiniteClipping, 1.0, 6.0),
+            (DistortionMode::ExponentialSoftClipping, 0.5, 4.0),
+        ] {
+            let settings = shaper_settings(&mode, amount, &custom_curve);
+            let off = aliased_energy(Antialiasing::Off, &settings, gain);
+            let first = aliased_energy(Antialiasing::FirstOrder, &settings, gain);
+            let second = aliased_energy(Antialiasing::SecondOrder, &settings, gain);
+            assert!(first < off / 8.0, "first order ADAA {first} vs off {off}");
+            assert!(
+                second < off / 8.0,
+                "second order ADAA {second} vs off {off}"
+            );
+        }
+    }
+
+    #[test]
+    fn adaa_constant_input() {
+        let custom_curve = TransferCurve::default();
+        for mode in [
+            DistortionMode::ArcTangent,
+            DistortionMode::Cubic,
+            DistortionMode::InfiniteClipping,
+            DistortionMode::ExponentialSoftClipping,
+        ] {
+            let settings = shaper_settings(&mode, 0.5, &custom_curve);
+            let expected = distortion_shaper(0.5, &settings);
+            for antialiasing in [Antialiasing::FirstOrder, Antialiasing::SecondOrder] {
+                // The input settles exponentially, so the differences between samples shrink past
+                // `ADAA_TOLERANCE` and the output has to cross over to the fallback smoothly
+                let mut adaa = Adaa::default();
+                let mut previous = adaa.process(0.51, antialiasing, &settings);
+                for idx in 1..512 {
+                    let input = 0.5 + 0.01 * 0.9f32.powi(idx);
+                    let output = adaa.process(input, antialiasing, &settings);
+                    assert!(output.is_finite());
+                    if idx > 2 {
+                        assert!((output - previous).abs() < 1e-3, "jump at sample {idx}");
+                    }
+                    previous = output;
+                }
+                assert!((previous - expected).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn bit_depth_state_migration() {
+        let mut state = PluginState {
+            version: String::new(),
+            params: [(String::from("bit-crush-bits"), ParamValue::I32(6))].into(),
+            fields: Default::default(),
+        };
+        HackAudio::filter_state(&mut state);
+
+        assert!(!state.params.contains_key("bit-crush-bits"));
+        assert!(matches!(
+            state.params.get("bit-crush-depth"),
+            Some(ParamValue::F32(bits)) if *bits == 6.0
+        ));
+    }
+
+    #[test]
+    fn sanitize_custom_curve_points() {
+        let mut points = default_custom_curve();
+        points[2].1 = 0.25;
+        let edited = points.clone();
+        assert!(!sanitize_custom_curve(&mut points));
+        assert_eq!(points, edited);
+
+        for mut points in [
+            Vec::new(),
+            vec![(-1.0, -1.0)],
+            vec![(-1.0, -1.0), (0.5, 0.0), (0.0, 0.0), (1.0, 1.0)],
+            vec![(-0.5, -1.0), (1.0, 1.0)],
+        ] {
+            assert!(sanitize_custom_curve(&mut points));
+            assert_eq!(points, default_custom_curve());
+        }
+    }
+
+    /// Runs a stereo 1 kHz sine through the meter, with every segment given as a level in dBFS and
+    /// a length in seconds. Returns the last readout.
+    fn measure_loudness(sample_rate: f32, segments: &[(f32, f32)]) -> LoudnessReadout {
+        let mut meter = LoudnessMeter::default();
+        meter.set_sample_rate(sample_rate);
+
+        let mut readout = LoudnessReadout::default();
+        let mut phase_idx = 0_u64;
+        for &(level, seconds) in segments {
+            let amplitude = db_to_gain(level);
+            for _ in 0..(seconds * sample_rate) as usize {
+                let phase = std::f64::consts::TAU * 1000.0 * phase_idx as f64 / sample_rate as f64;
+                let sample = amplitude * phase.sin() as f32;
+                phase_idx += 1;
+                if let Some(new_readout) = meter.process(sample, Some(sample)) {
+                    readout = new_readout;
+                }
+            }
+        }
+
+        readout
+    }
+
+    #[test]
+    fn loudness_tech_3341_stationary_sine() {
+        let readout = measure_loudness(44100.0, &[(-23.0, 20.0)]);
+        assert!((readout.momentary + 23.0).abs() <= 0.1, "{readout:?}");
+        assert!((readout.short_term + 23.0).abs() <= 0.1, "{readout:?}");
+        assert!((readout.integrated + 23.0).abs() <= 0.1, "{readout:?}");
+    }
+
+    #[test]
+    fn loudness_tech_3341_gating() {
+        // The -36 dBFS parts fall below the relative gate
+        let readout = measure_loudness(48000.0, &[(-36.0, 10.0), (-23.0, 60.0), (-36.0, 10.0)]);
+        assert!((readout.integrated + 23.0).abs() <= 0.1, "{readout:?}");
+    }
+
+    #[test]
+    fn loudness_tech_3342_range() {
+        let readout = measure_loudness(48000.0, &[(-20.0, 20.0), (-30.0, 20.0)]);
+        assert!((readout.range - 10.0).abs() <= 1.0, "{readout:?}");
+
+        let readout = measure_loudness(48000.0, &[(-20.0, 20.0), (-15.0, 20.0)]);
+        assert!((readout.range - 5.0).abs() <= 1.0, "{readout:?}");
+    }
+
+    #[test]
+    fn loudness_true_peak() {
+        // A sine at a quarter of the sample rate, shifted by 45 degrees, only ever hits 0.707 on
+        // the actual samples while the waveform in between reaches full scale
+        let mut meter = LoudnessMeter::default();
+        meter.set_sample_rate(48000.0);
+        let mut readout = LoudnessReadout::default();
+        let mut sample_peak = 0.0_f32;
+        for idx in 0..48000 {
+            let phase = std::f32::consts::TAU * idx as f32 / 4.0 + std::f32::consts::FRAC_PI_4;
+            let sample = phase.sin();
+            sample_peak = sample_peak.max(sample.abs());
+            if let Some(new_readout) = meter.process(sample, None) {
+                readout = new_readout;
+            }
+        }
+
+        assert!(util::gain_to_db(sample_peak) < -2.9);
+        assert!(readout.true_peak.abs() <= 0.5, "{readout:?}");
+    }
+
+    #[test]
+    fn loudness_reset() {
+        let sine = |amplitude: f32, length: usize| {
+            (0..length)
+                .map(|idx| amplitude * (std::f32::consts::TAU * idx as f32 / 48.0).sin())
+                .collect::<Vec<f32>>()
+        };
+
+        let params = PluginParams::default();
+        let (mut input, mut output) = TripleBuffer::default().split();
+        let mut meter = LoudnessMeter::default();
+        meter.set_sample_rate(48000.0);
+        let mut fresh_meter = LoudnessMeter::default();
+        fresh_meter.set_sample_rate(48000.0);
+
+        let mut left = sine(0.5, 48000);
+        let mut right = sine(0.5, 48000);
+        loudness_meter_process(
+            &[left.as_mut_slice(), right.as_mut_slice()],
+            &params,
+            &mut meter,
+            &mut input,
+        );
+        assert!(output.read().integrated.is_finite());
+
+        params.loudness_reset.store(true, Ordering::Relaxed);
+        loudness_meter_process(&[], &params, &mut meter, &mut input);
+        assert!(!params.loudness_reset.load(Ordering::Relaxed));
+        assert_eq!(*output.read(), LoudnessReadout::default());
+
+        // Whatever comes after the reset is measured as if nothing came before it
+        let mut quiet = sine(0.1, 24000);
+        let expected = quiet.iter().fold(None, |readout, &sample| {
+            fresh_meter.process(sample, None).or(readout)
+        });
+        loudness_meter_process(&[quiet.as_mut_slice()], &params, &mut meter, &mut input);
+        assert_eq!(Some(*output.read()), expected);
+    }
+}